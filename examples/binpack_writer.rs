@@ -15,7 +15,7 @@ fn main() {
         .read(true)
         .write(true)
         .create(true)
-        .append(false)
+        .truncate(true)
         .open("mynew.binpack")
         .unwrap();
 
@@ -23,7 +23,7 @@ fn main() {
 
     // When writing a binpack entries must preferably be a contiuation of the previous entry
     // to achieve the best compression ratio.
-    let entries = vec![
+    let entries = [
         TrainingDataEntry {
             pos: Position::from_fen("1q5b/1r5k/4p2p/1b2P1pN/3p4/6PP/1nP3B1/1Q2B1K1 w - - 0 35")
                 .unwrap(),
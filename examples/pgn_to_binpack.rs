@@ -0,0 +1,44 @@
+use std::fs::OpenOptions;
+
+use sfbinpack::{chess::pgn::parse_pgn, CompressedTrainingDataEntryWriter};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    let input = args.get(1).map_or("./test/sample.pgn", String::as_str);
+    let output = args.get(2).map_or("sample.binpack", String::as_str);
+
+    let text = std::fs::read_to_string(input).expect("cannot read input");
+    let games = parse_pgn(&text).unwrap();
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(output)
+        .expect("cannot open output");
+
+    let mut writer = CompressedTrainingDataEntryWriter::new(file).unwrap();
+
+    let mut num_entries = 0;
+
+    for game in &games {
+        // PGNs carry no evaluations, so every move gets a placeholder score of 0.
+        // Games with an unknown result ("*") are stored as draws.
+        let moves = game.moves.iter().map(|&mv| (mv, 0)).collect::<Vec<_>>();
+
+        writer
+            .write_game(&game.start, &moves, game.result.unwrap_or(0))
+            .unwrap();
+
+        num_entries += moves.len();
+    }
+
+    println!(
+        "Wrote {} entries from {} games to {}",
+        num_entries,
+        games.len(),
+        output
+    );
+}
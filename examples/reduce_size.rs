@@ -62,7 +62,7 @@ fn main() {
         writer.write_entry(&entry).unwrap();
         written += 1;
 
-        if written % 1000 == 0 {
+        if written.is_multiple_of(1000) {
             let out_size = writer.written_bytes();
             if out_size >= target_bytes {
                 break;
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::{chess::r#move::Move, TrainingDataEntry};
+
+/// Reorder `entries` so that every game forms a contiguous chain again, e.g. after
+/// shuffling or filtering. An entry is linked to an entry whose position is reached
+/// by its move, ignoring the move counters, and a successor with the consecutive ply
+/// is preferred. Games are emitted in the order their stems appear in the input,
+/// an entry continuing no other entry is the stem of a game. A repeated position
+/// can link entries into a cycle, which is emitted from its first entry in the input.
+///
+/// Within a chain the ply, result and move counters are renumbered from the stem,
+/// so that every entry is a [`TrainingDataEntry::is_continuation`] of the one before.
+pub fn normalize_chains(entries: &mut Vec<TrainingDataEntry>) {
+    let mut by_position: HashMap<u128, Vec<usize>> = HashMap::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        by_position.entry(entry.pos.key128()).or_default().push(i);
+    }
+
+    let mut successor = vec![None; entries.len()];
+    let mut has_predecessor = vec![false; entries.len()];
+
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.mv == Move::null() {
+            continue;
+        }
+
+        let next = entry.pos.after_move(entry.mv).key128();
+
+        let Some(candidates) = by_position.get(&next) else {
+            continue;
+        };
+
+        let mut unclaimed = candidates.iter().filter(|&&j| !has_predecessor[j]);
+
+        if let Some(&j) = unclaimed
+            .clone()
+            .find(|&&j| entry.ply + 1 == entries[j].ply)
+            .or_else(|| unclaimed.next())
+        {
+            successor[i] = Some(j);
+            has_predecessor[j] = true;
+        }
+    }
+
+    let mut chained = Vec::with_capacity(entries.len());
+    let mut emitted = vec![false; entries.len()];
+
+    // stems first, then whatever is left in cycles, e.g. from repeated positions
+    let stems = (0..entries.len()).filter(|&i| !has_predecessor[i]);
+
+    for start in stems.chain(0..entries.len()) {
+        let mut current = Some(start).filter(|&i| !emitted[i]);
+        let mut previous: Option<TrainingDataEntry> = None;
+
+        while let Some(i) = current {
+            let mut entry = entries[i];
+
+            if let Some(previous) = previous {
+                entry.pos = previous.pos.after_move(previous.mv);
+                entry.ply = previous.ply + 1;
+                entry.result = -previous.result;
+            }
+
+            chained.push(entry);
+            emitted[i] = true;
+            previous = Some(entry);
+            current = successor[i].filter(|&j| !emitted[j]);
+        }
+    }
+
+    *entries = chained;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::position::Position;
+    use crate::CompressedTrainingDataEntryReader;
+
+    #[test]
+    fn test_normalize_chains() {
+        let mut reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+        let mut game = Vec::new();
+        while reader.has_next() {
+            game.push(reader.next());
+        }
+
+        // a second game from the start position
+        let mut pos = Position::new();
+        for (i, uci) in ["e2e4", "e7e5", "g1f3", "b8c6"].iter().enumerate() {
+            let mv = Move::from_uci(&pos, uci).unwrap();
+            game.push(TrainingDataEntry {
+                pos,
+                mv,
+                score: 20 - i as i16,
+                ply: i as u16,
+                result: if i % 2 == 0 { 1 } else { -1 },
+            });
+            pos.do_move(mv);
+        }
+
+        let mut entries = vec![
+            game[5], game[2], game[3], game[0], game[6], game[1], game[4],
+        ];
+        normalize_chains(&mut entries);
+
+        assert_eq!(entries.len(), game.len());
+
+        let stems = (1..entries.len())
+            .filter(|&i| !entries[i - 1].is_continuation(&entries[i]))
+            .count()
+            + 1;
+        assert_eq!(stems, 2);
+
+        // the stem of the game from the start position comes first in the input
+        assert_eq!(entries[..4], game[3..]);
+        assert_eq!(entries[4..], game[..3]);
+    }
+
+    #[test]
+    fn test_normalize_chains_renumbers() {
+        // the knights return home, so the start position appears twice
+        let mut pos = Position::new();
+        let mut game = Vec::new();
+        for (i, uci) in ["g1f3", "g8f6", "f3g1", "f6g8", "e2e4"].iter().enumerate() {
+            let mv = Move::from_uci(&pos, uci).unwrap();
+            game.push(TrainingDataEntry {
+                pos,
+                mv,
+                score: 0,
+                ply: i as u16,
+                result: if i % 2 == 0 { 1 } else { -1 },
+            });
+            pos.do_move(mv);
+        }
+
+        // gaps and inconsistent plies and results after the stem
+        let mut corrupt = game.clone();
+        for (entry, ply) in corrupt.iter_mut().zip([0, 7, 3, 3, 20]).skip(1) {
+            entry.ply = ply;
+            entry.pos.set_ply(ply);
+            entry.result = 0;
+        }
+
+        let mut entries = vec![corrupt[3], corrupt[1], corrupt[4], corrupt[0], corrupt[2]];
+        normalize_chains(&mut entries);
+        assert_eq!(entries, game);
+
+        // without plies to go by the repetition links the last knight move back to
+        // the stem, the cycle is still emitted starting at its first input entry
+        for entry in &mut corrupt {
+            entry.ply = 0;
+        }
+        let mut entries = corrupt.clone();
+        normalize_chains(&mut entries);
+        assert_eq!(entries[0], corrupt[4]);
+        assert_eq!(entries[1..], game[..4]);
+    }
+}
@@ -1,6 +1,13 @@
 use crate::chess::{
-    bitboard::Bitboard, castling_rights::CastlingRights, color::Color, coords::Square,
-    hyperbola::HyperbolaQsc, piece::Piece, piecetype::PieceType, position::Position, r#move::Move,
+    bitboard::Bitboard,
+    castling_rights::CastlingRights,
+    color::Color,
+    coords::{Rank, Square},
+    hyperbola::HyperbolaQsc,
+    piece::Piece,
+    piecetype::PieceType,
+    position::Position,
+    r#move::Move,
 };
 
 use arrayvec::ArrayVec;
@@ -23,21 +30,221 @@ fn pop_lsb(bb: &mut u64) -> Square {
 /// Return every pseudo-legal move for the current position.
 pub fn pseudo_legal_moves(pos: &Position) -> ArrayVec<Move, 256> {
     let mut moves = ArrayVec::new();
+    pseudo_legal_moves_into(pos, &mut moves);
+    moves
+}
+
+/// Clear `moves` and fill it with every pseudo-legal move for the current position,
+/// reusing a caller owned buffer across calls.
+pub fn pseudo_legal_moves_into(pos: &Position, moves: &mut ArrayVec<Move, 256>) {
+    moves.clear();
+    for_each_pseudo_legal_move(pos, |mv| moves.push(mv));
+}
+
+/// Call `push` with every pseudo-legal move for the current position as it is generated,
+/// e.g. to count or filter moves without collecting them first.
+pub fn for_each_pseudo_legal_move(pos: &Position, mut push: impl FnMut(Move)) {
+    let push = &mut push;
     let side = pos.side_to_move();
     let occupancy = Bitboard::new(pos.occupied().bits());
 
-    generate_pawn_moves(pos, side, &mut moves);
-    generate_piece_moves::<Knight>(pos, side, occupancy, &mut moves);
-    generate_piece_moves::<Bishop>(pos, side, occupancy, &mut moves);
-    generate_piece_moves::<Rook>(pos, side, occupancy, &mut moves);
-    generate_piece_moves::<Queen>(pos, side, occupancy, &mut moves);
-    generate_piece_moves::<King>(pos, side, occupancy, &mut moves);
-    generate_castling_moves(pos, side, &mut moves);
+    let targets = !pos.pieces_bb(side);
+
+    generate_pawn_moves(pos, side, push);
+    generate_piece_moves::<Knight>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Bishop>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Rook>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Queen>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<King>(pos, side, occupancy, targets, push);
+    generate_castling_moves(pos, side, push);
+}
+
+/// Return true if `mv` is one of the [`pseudo_legal_moves`] of the position,
+/// checking the single move instead of generating all of them.
+pub fn is_pseudo_legal(pos: &Position, mv: Move) -> bool {
+    let side = pos.side_to_move();
+    let (from_sq, to_sq) = (mv.from(), mv.to());
+
+    if from_sq.is_none() || to_sq.is_none() {
+        return false;
+    }
+
+    let piece = pos.piece_at(from_sq);
+
+    if piece.is_none() || piece.color() != side {
+        return false;
+    }
+
+    if mv.is_castle() {
+        return piece.piece_type() == PieceType::King && is_castle_pseudo_legal(pos, side, mv);
+    }
+
+    let target = pos.piece_at(to_sq);
+
+    if target.is_some() && target.color() == side {
+        return false;
+    }
+
+    match piece.piece_type() {
+        PieceType::Pawn => is_pawn_move_pseudo_legal(pos, side, mv),
+        pt => {
+            mv == Move::normal(from_sq, to_sq)
+                && piece_attacks(pt, from_sq, pos.occupied()).is_set(to_sq.index())
+        }
+    }
+}
+
+fn is_pawn_move_pseudo_legal(pos: &Position, side: Color, mv: Move) -> bool {
+    let (from_sq, to_sq) = (mv.from(), mv.to());
+    let target = pos.piece_at(to_sq);
+
+    if pawn(side, from_sq).is_set(to_sq.index()) {
+        let ep_square = pos.ep_square();
+
+        if ep_square.is_some() && to_sq == ep_square {
+            return mv == Move::en_passant(from_sq, to_sq);
+        }
+
+        if target.is_none() {
+            return false;
+        }
+    } else {
+        let direction = if side == Color::White { 8 } else { -8 };
+        let one_step = from_sq.index() as i32 + direction;
+        let distance = to_sq.index() as i32 - from_sq.index() as i32;
+
+        if target.is_some() {
+            return false;
+        }
+
+        if distance == 2 * direction {
+            // Double push, never onto the promotion rank
+            return from_sq.rank() == Rank::pawn_start_rank(side)
+                && pos.piece_at(Square::new(one_step as u32)).is_none()
+                && mv == Move::normal(from_sq, to_sq);
+        }
+
+        if distance != direction {
+            return false;
+        }
+    }
+
+    if to_sq.rank() == Rank::promotion_rank(side) {
+        PROMOTION_PIECES
+            .iter()
+            .any(|&pt| mv == Move::promotion(from_sq, to_sq, Piece::new(pt, side)))
+    } else {
+        mv == Move::normal(from_sq, to_sq)
+    }
+}
+
+fn is_castle_pseudo_legal(pos: &Position, side: Color, mv: Move) -> bool {
+    let king_sq = pos.king_sq(side);
+
+    if mv != Move::castle(king_sq, mv.to()) {
+        return false;
+    }
+
+    let castle_right = match (side, mv.to()) {
+        (Color::White, Square::H1) => CastlingRights::WHITE_KING_SIDE,
+        (Color::White, Square::A1) => CastlingRights::WHITE_QUEEN_SIDE,
+        (Color::Black, Square::H8) => CastlingRights::BLACK_KING_SIDE,
+        (Color::Black, Square::A8) => CastlingRights::BLACK_QUEEN_SIDE,
+        _ => return false,
+    };
+
+    pieces_attacking_square(king_sq, side, pos).bits() == 0
+        && can_castle(pos, side, castle_right, mv.to())
+}
+
+/// Return the pseudo-legal captures and promotions for the current position,
+/// including en passant and all four promotion pieces, e.g. for a quiescence search.
+pub fn capture_moves(pos: &Position) -> ArrayVec<Move, 256> {
+    let mut moves = ArrayVec::new();
+    let push = &mut |mv| moves.push(mv);
+    let side = pos.side_to_move();
+    let occupancy = pos.occupied();
+    let targets = pos.pieces_bb(!side);
+
+    let mut pawns = pos.pieces_bb_color(side, PieceType::Pawn).bits();
+    let direction = if side == Color::White { 8 } else { -8 };
+    let promotion_rank_start = if side == Color::White { 56 } else { 0 };
+    let promotion_rank_end = if side == Color::White { 64 } else { 8 };
+
+    while pawns != 0 {
+        let from_sq = pop_lsb(&mut pawns);
+        let one_step = from_sq.index() as i32 + direction;
+
+        // the only quiet moves kept are promotion pushes
+        if (promotion_rank_start..promotion_rank_end).contains(&one_step)
+            && pos.piece_at(Square::new(one_step as u32)).is_none()
+        {
+            add_promotions(from_sq, Square::new(one_step as u32), side, push);
+        }
+
+        generate_pawn_captures(
+            pos,
+            side,
+            from_sq,
+            promotion_rank_start,
+            promotion_rank_end,
+            push,
+        );
+    }
+
+    generate_piece_moves::<Knight>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Bishop>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Rook>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Queen>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<King>(pos, side, occupancy, targets, push);
 
     moves
 }
 
-fn generate_pawn_moves(pos: &Position, side: Color, moves: &mut ArrayVec<Move, 256>) {
+/// Return the pseudo-legal moves which [`capture_moves`] leaves out,
+/// pawn pushes without promotions, quiet piece moves and castling.
+pub fn quiet_moves(pos: &Position) -> ArrayVec<Move, 256> {
+    let mut moves = ArrayVec::new();
+    let push = &mut |mv| moves.push(mv);
+    let side = pos.side_to_move();
+    let occupancy = pos.occupied();
+    let targets = !occupancy;
+
+    let mut pawns = pos.pieces_bb_color(side, PieceType::Pawn).bits();
+    let direction = if side == Color::White { 8 } else { -8 };
+    let promotion_rank_start = if side == Color::White { 56 } else { 0 };
+    let promotion_rank_end = if side == Color::White { 64 } else { 8 };
+
+    while pawns != 0 {
+        let from_sq = pop_lsb(&mut pawns);
+
+        // promotion pushes are part of the captures
+        if !(promotion_rank_start..promotion_rank_end)
+            .contains(&(from_sq.index() as i32 + direction))
+        {
+            generate_pawn_pushes(
+                pos,
+                side,
+                from_sq,
+                direction,
+                promotion_rank_start,
+                promotion_rank_end,
+                push,
+            );
+        }
+    }
+
+    generate_piece_moves::<Knight>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Bishop>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Rook>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<Queen>(pos, side, occupancy, targets, push);
+    generate_piece_moves::<King>(pos, side, occupancy, targets, push);
+    generate_castling_moves(pos, side, push);
+
+    moves
+}
+
+fn generate_pawn_moves(pos: &Position, side: Color, push: &mut impl FnMut(Move)) {
     let mut pawns = pos.pieces_bb_color(side, PieceType::Pawn).bits();
     let direction = if side == Color::White { 8 } else { -8 };
     let promotion_rank_start = if side == Color::White { 56 } else { 0 };
@@ -53,7 +260,7 @@ fn generate_pawn_moves(pos: &Position, side: Color, moves: &mut ArrayVec<Move, 2
             direction,
             promotion_rank_start,
             promotion_rank_end,
-            moves,
+            push,
         );
 
         generate_pawn_captures(
@@ -62,7 +269,7 @@ fn generate_pawn_moves(pos: &Position, side: Color, moves: &mut ArrayVec<Move, 2
             from_sq,
             promotion_rank_start,
             promotion_rank_end,
-            moves,
+            push,
         );
     }
 }
@@ -74,29 +281,27 @@ fn generate_pawn_pushes(
     direction: i32,
     promotion_start: i32,
     promotion_end: i32,
-    moves: &mut ArrayVec<Move, 256>,
+    push: &mut impl FnMut(Move),
 ) {
     let start_rank = if side == Color::White { 1 } else { 6 };
 
     let one_step = from_sq.index() as i32 + direction;
-    if !(0..64).contains(&one_step) || pos.piece_at(Square::new(one_step as u32)) != Piece::none() {
+    if !(0..64).contains(&one_step) || pos.piece_at(Square::new(one_step as u32)).is_some() {
         return;
     }
 
     let to_sq = Square::new(one_step as u32);
 
     if (promotion_start..promotion_end).contains(&one_step) {
-        add_promotions(from_sq, to_sq, side, moves);
+        add_promotions(from_sq, to_sq, side, push);
     } else {
-        moves.push(Move::normal(from_sq, to_sq));
+        push(Move::normal(from_sq, to_sq));
 
         // Double push
         if from_sq.index() / 8 == start_rank {
             let two_step = one_step + direction;
-            if (0..64).contains(&two_step)
-                && pos.piece_at(Square::new(two_step as u32)) == Piece::none()
-            {
-                moves.push(Move::normal(from_sq, Square::new(two_step as u32)));
+            if (0..64).contains(&two_step) && pos.piece_at(Square::new(two_step as u32)).is_none() {
+                push(Move::normal(from_sq, Square::new(two_step as u32)));
             }
         }
     }
@@ -108,7 +313,7 @@ fn generate_pawn_captures(
     from_sq: Square,
     promotion_start: i32,
     promotion_end: i32,
-    moves: &mut ArrayVec<Move, 256>,
+    push: &mut impl FnMut(Move),
 ) {
     let mut attacks = pawn(side, from_sq).bits();
     let ep_square = pos.ep_square();
@@ -116,17 +321,17 @@ fn generate_pawn_captures(
     while attacks != 0 {
         let to_sq = pop_lsb(&mut attacks);
 
-        if ep_square != Square::NONE && to_sq == ep_square {
-            moves.push(Move::en_passant(from_sq, to_sq));
+        if ep_square.is_some() && to_sq == ep_square {
+            push(Move::en_passant(from_sq, to_sq));
             continue;
         }
 
         let target = pos.piece_at(to_sq);
-        if target != Piece::none() && target.color() != side {
+        if target.is_some() && target.color() != side {
             if (promotion_start..promotion_end).contains(&(to_sq.index() as i32)) {
-                add_promotions(from_sq, to_sq, side, moves);
+                add_promotions(from_sq, to_sq, side, push);
             } else {
-                moves.push(Move::normal(from_sq, to_sq));
+                push(Move::normal(from_sq, to_sq));
             }
         }
     }
@@ -191,25 +396,22 @@ fn generate_piece_moves<P: PieceMovement>(
     pos: &Position,
     side: Color,
     occupancy: Bitboard,
-    moves: &mut ArrayVec<Move, 256>,
+    target_mask: Bitboard,
+    push: &mut impl FnMut(Move),
 ) {
     let mut pieces = pos.pieces_bb_color(side, P::piece_type()).bits();
 
     while pieces != 0 {
         let from_sq = pop_lsb(&mut pieces);
-        let mut targets = P::get_attacks(from_sq, occupancy).bits();
+        let mut targets = (P::get_attacks(from_sq, occupancy) & target_mask).bits();
 
         while targets != 0 {
             let to_sq = pop_lsb(&mut targets);
-            let target = pos.piece_at(to_sq);
-
-            if target == Piece::none() || target.color() != side {
-                moves.push(Move::normal(from_sq, to_sq));
-            }
+            push(Move::normal(from_sq, to_sq));
         }
     }
 }
-fn generate_castling_moves(pos: &Position, side: Color, moves: &mut ArrayVec<Move, 256>) {
+fn generate_castling_moves(pos: &Position, side: Color, push: &mut impl FnMut(Move)) {
     let king_sq = pos.king_sq(side);
 
     // Can't castle if in check
@@ -220,13 +422,13 @@ fn generate_castling_moves(pos: &Position, side: Color, moves: &mut ArrayVec<Mov
     match side {
         #[rustfmt::skip]
         Color::White => {
-            try_castle(pos, side, moves, CastlingRights::WHITE_KING_SIDE, king_sq, Square::H1);
-            try_castle(pos, side, moves, CastlingRights::WHITE_QUEEN_SIDE, king_sq, Square::A1);
+            try_castle(pos, side, push, CastlingRights::WHITE_KING_SIDE, king_sq, Square::H1);
+            try_castle(pos, side, push, CastlingRights::WHITE_QUEEN_SIDE, king_sq, Square::A1);
         }
         #[rustfmt::skip]
         Color::Black => {
-            try_castle(pos, side, moves, CastlingRights::BLACK_KING_SIDE, king_sq, Square::H8);
-            try_castle(pos, side, moves, CastlingRights::BLACK_QUEEN_SIDE, king_sq, Square::A8);
+            try_castle(pos, side, push, CastlingRights::BLACK_KING_SIDE, king_sq, Square::H8);
+            try_castle(pos, side, push, CastlingRights::BLACK_QUEEN_SIDE, king_sq, Square::A8);
         }
     }
 }
@@ -234,14 +436,22 @@ fn generate_castling_moves(pos: &Position, side: Color, moves: &mut ArrayVec<Mov
 fn try_castle(
     pos: &Position,
     side: Color,
-    moves: &mut ArrayVec<Move, 256>,
+    push: &mut impl FnMut(Move),
     castle_right: CastlingRights,
     king_sq: Square,
     rook_sq: Square,
 ) {
+    if can_castle(pos, side, castle_right, rook_sq) {
+        push(Move::castle(king_sq, rook_sq));
+    }
+}
+
+// The right is held and the path is empty and not attacked, the king itself
+// being in check is left to the caller
+fn can_castle(pos: &Position, side: Color, castle_right: CastlingRights, rook_sq: Square) -> bool {
     let rights = pos.castling_rights();
     if !rights.contains(castle_right) {
-        return;
+        return false;
     }
 
     // Determine squares based on rook position
@@ -251,27 +461,18 @@ fn try_castle(
         Square::A1 => (&[Square::C1, Square::D1][..], &[Square::B1, Square::C1, Square::D1][..]),
         Square::H8 => (&[Square::F8, Square::G8][..], &[Square::F8, Square::G8][..]),
         Square::A8 => (&[Square::C8, Square::D8][..], &[Square::B8, Square::C8, Square::D8][..]),
-        _ => return,
+        _ => return false,
     };
 
-    for &sq in path_squares {
-        if pos.piece_at(sq) != Piece::none() {
-            return;
-        }
-    }
-
-    for &sq in check_path_squares {
-        if pieces_attacking_square(sq, side, pos).bits() != 0 {
-            return;
-        }
-    }
-
-    moves.push(Move::castle(king_sq, rook_sq));
+    path_squares.iter().all(|&sq| pos.piece_at(sq).is_none())
+        && check_path_squares
+            .iter()
+            .all(|&sq| pieces_attacking_square(sq, side, pos).bits() == 0)
 }
 
-fn add_promotions(from_sq: Square, to_sq: Square, side: Color, moves: &mut ArrayVec<Move, 256>) {
+fn add_promotions(from_sq: Square, to_sq: Square, side: Color, push: &mut impl FnMut(Move)) {
     for &piece_type in PROMOTION_PIECES.iter() {
-        moves.push(Move::promotion(
+        push(Move::promotion(
             from_sq,
             to_sq,
             Piece::new(piece_type, side),
@@ -323,6 +524,74 @@ pub fn king(sq: Square) -> Bitboard {
     Bitboard::new(KING_ATTACKS[sq.index() as usize])
 }
 
+/// Get the union of the knight attacks of all squares in the set.
+pub fn knight_attacks_bb(knights: Bitboard) -> Bitboard {
+    const NOT_A: u64 = 0xFEFE_FEFE_FEFE_FEFE;
+    const NOT_AB: u64 = 0xFCFC_FCFC_FCFC_FCFC;
+    const NOT_H: u64 = 0x7F7F_7F7F_7F7F_7F7F;
+    const NOT_GH: u64 = 0x3F3F_3F3F_3F3F_3F3F;
+
+    let b = knights.bits();
+
+    Bitboard::new(
+        ((b << 17) & NOT_A)
+            | ((b << 15) & NOT_H)
+            | ((b << 10) & NOT_AB)
+            | ((b << 6) & NOT_GH)
+            | ((b >> 17) & NOT_H)
+            | ((b >> 15) & NOT_A)
+            | ((b >> 10) & NOT_GH)
+            | ((b >> 6) & NOT_AB),
+    )
+}
+
+/// Get the union of the king attacks of all squares in the set.
+pub fn king_attacks_bb(kings: Bitboard) -> Bitboard {
+    kings
+        .iter()
+        .fold(Bitboard::new(0), |attacks, sq| attacks | king(sq))
+}
+
+/// Get the squares strictly between two squares on a common rank, file or diagonal.
+/// Returns an empty bitboard if the squares are not aligned.
+pub fn between(a: Square, b: Square) -> Bitboard {
+    let bb_a = Bitboard::from_square(a);
+    let bb_b = Bitboard::from_square(b);
+
+    if rook(a, Bitboard::new(0)).sq_set(b) {
+        rook(a, bb_b) & rook(b, bb_a)
+    } else if bishop(a, Bitboard::new(0)).sq_set(b) {
+        bishop(a, bb_b) & bishop(b, bb_a)
+    } else {
+        Bitboard::new(0)
+    }
+}
+
+/// Get the full rank, file or diagonal going through both squares, including them.
+/// Returns an empty bitboard if the squares are not aligned.
+pub fn line(a: Square, b: Square) -> Bitboard {
+    let ends = Bitboard::from_square(a) | Bitboard::from_square(b);
+
+    if rook(a, Bitboard::new(0)).sq_set(b) {
+        rook(a, Bitboard::new(0)) & rook(b, Bitboard::new(0)) | ends
+    } else if bishop(a, Bitboard::new(0)).sq_set(b) {
+        bishop(a, Bitboard::new(0)) & bishop(b, Bitboard::new(0)) | ends
+    } else {
+        Bitboard::new(0)
+    }
+}
+
+/// Get the squares a non-king move must land on to resolve the checks on the king,
+/// the checker and the squares between it and the king.
+/// All squares if there are no checkers and none in double check, where only the king can move.
+pub fn in_check_mask(king: Square, checkers: Bitboard) -> Bitboard {
+    match checkers.count() {
+        0 => Bitboard::new(u64::MAX),
+        1 => between(king, checkers.lsb()) | checkers,
+        _ => Bitboard::new(0),
+    }
+}
+
 /// Get pseudo attacks for a given piece type, square, and occupied squares.
 pub fn piece_attacks(pt: PieceType, sq: Square, occupied: Bitboard) -> Bitboard {
     match pt {
@@ -414,7 +683,7 @@ static KING_ATTACKS: [u64; 64] = [
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chess::{piecetype::PieceType, position::Position, r#move::MoveType};
+    use crate::chess::{piecetype::PieceType, position::Position};
 
     const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
@@ -425,7 +694,7 @@ mod tests {
 
         let mut nodes = 0;
 
-        let moves = pseudo_legal_moves(&pos);
+        let moves = pseudo_legal_moves(pos);
 
         for mv in moves {
             let new_pos = pos.after_move(mv);
@@ -504,6 +773,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_between_and_line() {
+        assert_eq!(
+            between(Square::A1, Square::A8),
+            Bitboard::new(0x0001_0101_0101_0100)
+        );
+        assert_eq!(
+            between(Square::A1, Square::H8),
+            Bitboard::new(0x0040_2010_0804_0200)
+        );
+        assert_eq!(between(Square::A1, Square::new(1)), Bitboard::new(0));
+        assert_eq!(between(Square::A1, Square::new(11)), Bitboard::new(0));
+
+        assert_eq!(
+            line(Square::new(9), Square::new(18)),
+            Bitboard::new(0x8040_2010_0804_0201)
+        );
+        assert_eq!(line(Square::new(3), Square::new(5)), Bitboard::from_rank(0));
+        assert_eq!(line(Square::A1, Square::new(17)), Bitboard::new(0));
+    }
+
+    #[test]
+    fn test_attacks_bb() {
+        let knights = Bitboard::from_square(Square::B1) | Bitboard::from_square(Square::new(39));
+        assert_eq!(
+            knight_attacks_bb(knights),
+            knight(Square::B1) | knight(Square::new(39))
+        );
+
+        // every square, including the board edges
+        for sq in 0..64 {
+            let bb = Bitboard::from_square(Square::new(sq));
+            assert_eq!(knight_attacks_bb(bb), knight(Square::new(sq)));
+            assert_eq!(king_attacks_bb(bb), king(Square::new(sq)));
+        }
+
+        let kings = Bitboard::from_square(Square::E1) | Bitboard::from_square(Square::H8);
+        assert_eq!(king_attacks_bb(kings), king(Square::E1) | king(Square::H8));
+        assert_eq!(knight_attacks_bb(Bitboard::new(0)), Bitboard::new(0));
+    }
+
+    #[test]
+    fn test_in_check_mask() {
+        // the rook on e8 checks the king on e1
+        let pos = Position::from_fen("4r2k/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+        let checkers = pos.checkers();
+        assert_eq!(
+            in_check_mask(pos.king_sq(Color::White), checkers),
+            Bitboard::from_file(4) & !Bitboard::from_square(Square::E1)
+        );
+
+        // double check by the knight on d3 and the rook on e8
+        let pos = Position::from_fen("4r2k/8/8/8/8/3n4/8/4K2R w - - 0 1").unwrap();
+        let checkers = pos.checkers();
+        assert_eq!(checkers.count(), 2);
+        assert_eq!(
+            in_check_mask(pos.king_sq(Color::White), checkers),
+            Bitboard::new(0)
+        );
+
+        let pos = Position::new();
+        assert_eq!(
+            in_check_mask(pos.king_sq(Color::White), pos.checkers()),
+            Bitboard::new(u64::MAX)
+        );
+    }
+
     #[test]
     fn test_bishop_mask() {
         assert_eq!(
@@ -519,14 +855,73 @@ mod tests {
     #[test]
     fn test_pseudo_moves_startpos() {
         let pos = &Position::from_fen(STARTPOS).unwrap();
-        let moves = pseudo_legal_moves(&pos);
+        let moves = pseudo_legal_moves(pos);
         assert_eq!(moves.len(), 20);
     }
 
+    #[test]
+    fn test_pseudo_legal_moves_into() {
+        let mut moves = ArrayVec::new();
+
+        for fen in [
+            STARTPOS,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "k7/8/8/3pP3/8/8/8/6K1 w - d6 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+
+            // the buffer still holds the moves of the previous position
+            pseudo_legal_moves_into(&pos, &mut moves);
+            assert_eq!(moves, pseudo_legal_moves(&pos), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_is_pseudo_legal_matches_generator() {
+        for line in include_str!("../../test/standard.epd").lines() {
+            let fen = line.split(';').next().unwrap().trim();
+            if fen.is_empty() {
+                continue;
+            }
+
+            let pos = Position::from_fen(fen).unwrap();
+            let side = pos.side_to_move();
+            let moves = pseudo_legal_moves(&pos);
+
+            for (from, to) in (0..64).flat_map(|from| (0..64).map(move |to| (from, to))) {
+                let (from, to) = (Square::new(from), Square::new(to));
+
+                let mut candidates = vec![
+                    Move::normal(from, to),
+                    Move::en_passant(from, to),
+                    Move::castle(from, to),
+                    Move::promotion(from, to, Piece::new(PieceType::Queen, !side)),
+                ];
+                candidates.extend(
+                    PROMOTION_PIECES
+                        .iter()
+                        .map(|&pt| Move::promotion(from, to, Piece::new(pt, side))),
+                );
+
+                for mv in candidates {
+                    assert_eq!(
+                        is_pseudo_legal(&pos, mv),
+                        moves.contains(&mv),
+                        "{} {:?}",
+                        fen,
+                        mv
+                    );
+                }
+            }
+        }
+
+        assert!(!is_pseudo_legal(&Position::new(), Move::null()));
+    }
+
     #[test]
     fn test_knight_pseudo_moves() {
         let pos = &Position::from_fen("k7/8/8/3N4/8/8/8/6K1 w - - 0 1").unwrap();
-        let moves = pseudo_legal_moves(&pos);
+        let moves = pseudo_legal_moves(pos);
         let knight_moves = moves
             .iter()
             .filter(|m| pos.piece_at(m.from()).piece_type() == PieceType::Knight)
@@ -534,11 +929,55 @@ mod tests {
         assert_eq!(knight_moves, 8);
     }
 
+    #[test]
+    fn test_capture_moves() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r1b1k2r/1P3ppp/8/8/8/8/p4PPP/R3K1NR b KQkq - 0 20",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            let captures = capture_moves(&pos);
+
+            let expected: Vec<_> = pseudo_legal_moves(&pos)
+                .into_iter()
+                .filter(|mv| mv.is_promotion() || pos.captured_piece(*mv).is_some())
+                .collect();
+
+            assert_eq!(captures.len(), expected.len(), "{}", fen);
+            assert!(expected.iter().all(|mv| captures.contains(mv)), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_quiet_moves() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r1b1k2r/1P3ppp/8/8/8/8/p4PPP/R3K1NR b KQkq - 0 20",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            let quiets = quiet_moves(&pos);
+
+            let expected: Vec<_> = pseudo_legal_moves(&pos)
+                .into_iter()
+                .filter(|mv| !mv.is_promotion() && pos.captured_piece(*mv).is_none())
+                .collect();
+
+            assert_eq!(quiets.len(), expected.len(), "{}", fen);
+            assert!(expected.iter().all(|mv| quiets.contains(mv)), "{}", fen);
+        }
+    }
+
     #[test]
     fn test_en_passant_included() {
         let pos = &Position::from_fen("k7/8/8/3pP3/8/8/8/6K1 w - d6 0 1").unwrap();
-        let moves = pseudo_legal_moves(&pos);
-        assert!(moves.iter().any(|m| m.mtype() == MoveType::EnPassant));
+        let moves = pseudo_legal_moves(pos);
+        assert!(moves.iter().any(|m| m.is_en_passant()));
     }
 
     #[test]
@@ -2,7 +2,8 @@ use std::ops::{BitAnd, BitOr, BitOrAssign, Not};
 
 use crate::chess::coords::{File, Rank, Square};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// An empty bitboard by default
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Bitboard {
     data: u64,
 }
@@ -108,6 +109,45 @@ impl Bitboard {
     pub fn iter(&self) -> BitboardIterator {
         BitboardIterator { remaining: *self }
     }
+
+    /// Flip the board vertically, rank 1 becomes rank 8
+    pub const fn flip_vertical(&self) -> Self {
+        Self {
+            data: self.data.swap_bytes(),
+        }
+    }
+
+    /// Mirror the board horizontally, file a becomes file h
+    pub const fn mirror_horizontal(&self) -> Self {
+        Self {
+            data: self.data.reverse_bits().swap_bytes(),
+        }
+    }
+
+    /// Fill every set bit towards rank 8, including the bit itself
+    pub const fn north_fill(&self) -> Self {
+        let mut data = self.data;
+        data |= data << 8;
+        data |= data << 16;
+        data |= data << 32;
+        Self { data }
+    }
+
+    /// Fill every set bit towards rank 1, including the bit itself
+    pub const fn south_fill(&self) -> Self {
+        let mut data = self.data;
+        data |= data >> 8;
+        data |= data >> 16;
+        data |= data >> 32;
+        Self { data }
+    }
+
+    /// Every file with at least one set bit
+    pub const fn file_fill(&self) -> Self {
+        Self {
+            data: self.north_fill().data | self.south_fill().data,
+        }
+    }
 }
 
 pub struct BitboardIterator {
@@ -168,3 +208,89 @@ impl BitOrAssign for Bitboard {
         self.data |= rhs.data;
     }
 }
+
+impl From<u64> for Bitboard {
+    fn from(bits: u64) -> Self {
+        Self::new(bits)
+    }
+}
+
+impl From<Bitboard> for u64 {
+    fn from(bb: Bitboard) -> Self {
+        bb.bits()
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> Self {
+        let mut bb = Self::default();
+
+        for sq in iter {
+            bb |= Self::from_square(sq);
+        }
+
+        bb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flip_vertical() {
+        assert_eq!(
+            Bitboard::from_rank(0).flip_vertical(),
+            Bitboard::from_rank(7)
+        );
+        assert_eq!(
+            Bitboard::from_square(Square::new(12)).flip_vertical(),
+            Bitboard::from_square(Square::new(52))
+        );
+    }
+
+    #[test]
+    fn test_mirror_horizontal() {
+        assert_eq!(
+            Bitboard::from_file(0).mirror_horizontal(),
+            Bitboard::from_file(7)
+        );
+        assert_eq!(
+            Bitboard::from_square(Square::B1).mirror_horizontal(),
+            Bitboard::from_square(Square::G1)
+        );
+    }
+
+    #[test]
+    fn test_fills() {
+        let a2 = Bitboard::from_square(Square::new(8));
+        assert_eq!(a2.north_fill(), Bitboard::new(0x0101_0101_0101_0100));
+        assert_eq!(a2.south_fill(), Bitboard::new(0x0101));
+        assert_eq!(a2.file_fill(), Bitboard::from_file(0));
+
+        let pawns = Bitboard::new(0x0000_0000_1000_4200);
+        assert_eq!(
+            pawns.file_fill(),
+            Bitboard::from_file(1) | Bitboard::from_file(4) | Bitboard::from_file(6)
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        let b2 = Square::new(9);
+        let bb: Bitboard = [Square::A1, b2].into_iter().collect();
+        assert_eq!(bb, Bitboard::new(0x201));
+        assert_eq!(bb.iter().collect::<Bitboard>(), bb);
+
+        assert_eq!(Bitboard::from(0x201), bb);
+        assert_eq!(u64::from(bb), 0x201);
+        let bits: u64 = bb.into();
+        assert_eq!(bits, 0x201);
+
+        assert_eq!(Bitboard::default(), Bitboard::new(0));
+        assert_eq!(
+            std::iter::empty().collect::<Bitboard>(),
+            Bitboard::default()
+        );
+    }
+}
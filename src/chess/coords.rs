@@ -88,21 +88,16 @@ impl Square {
     }
 
     pub fn from_string(s: &str) -> Option<Self> {
-        if s.len() != 2 {
-            return None;
-        }
-
-        let file = s.chars().nth(0).unwrap();
-        let rank = s.chars().nth(1).unwrap();
+        let mut chars = s.chars();
 
-        let file = file as i32 - 'a' as i32;
-        let rank = rank as i32 - '1' as i32;
+        let file = File::from_char(chars.next()?)?;
+        let rank = Rank::from_char(chars.next()?)?;
 
-        if Self::is_valid(rank as i64, file as i64) {
-            Some(Self::from_rank_file(rank as i64, file as i64))
-        } else {
-            None
+        if chars.next().is_some() {
+            return None;
         }
+
+        Some(Self::new(rank.index * 8 + file.index))
     }
 
     #[must_use]
@@ -110,6 +105,18 @@ impl Square {
         self.index
     }
 
+    /// Returns true if this is `Square::NONE`
+    #[must_use]
+    pub const fn is_none(self) -> bool {
+        self.index == Self::NONE.index
+    }
+
+    /// Returns true if this is a square on the board
+    #[must_use]
+    pub const fn is_some(self) -> bool {
+        !self.is_none()
+    }
+
     #[must_use]
     pub const fn file(self) -> File {
         File::new(self.index & 7)
@@ -131,6 +138,49 @@ impl Square {
         })
     }
 
+    /// Mirror the square vertically, e.g. a1 to a8
+    #[must_use]
+    pub const fn flip_rank(self) -> Self {
+        Self {
+            index: self.index ^ 56,
+        }
+    }
+
+    /// Mirror the square horizontally, e.g. a1 to h1
+    #[must_use]
+    pub const fn flip_file(self) -> Self {
+        Self {
+            index: self.index ^ 7,
+        }
+    }
+
+    /// Mirror the square along the a1-h8 diagonal, e.g. b1 to a2
+    #[must_use]
+    pub const fn flip_diag(self) -> Self {
+        Self {
+            index: ((self.index & 7) << 3) | (self.index >> 3),
+        }
+    }
+
+    /// Number of king moves between the squares, the Chebyshev distance
+    #[must_use]
+    pub const fn distance(a: Self, b: Self) -> u8 {
+        let files = (a.index & 7).abs_diff(b.index & 7);
+        let ranks = (a.index >> 3).abs_diff(b.index >> 3);
+
+        if files > ranks {
+            files as u8
+        } else {
+            ranks as u8
+        }
+    }
+
+    /// Sum of the file and rank differences between the squares
+    #[must_use]
+    pub const fn manhattan_distance(a: Self, b: Self) -> u8 {
+        ((a.index & 7).abs_diff(b.index & 7) + (a.index >> 3).abs_diff(b.index >> 3)) as u8
+    }
+
     /// Check if the square is within bounds
     #[must_use]
     pub const fn is_valid(r: i64, f: i64) -> bool {
@@ -215,11 +265,31 @@ impl File {
     pub const fn from_u32(index: u32) -> Self {
         Self { index }
     }
+
+    /// Mirror the file, a to h
+    pub const fn flip(self) -> Self {
+        Self {
+            index: 7 - self.index,
+        }
+    }
+
+    /// Parse a file from its letter 'a' to 'h'
+    pub const fn from_char(c: char) -> Option<Self> {
+        match c {
+            'a'..='h' => Some(Self::new(c as u32 - 'a' as u32)),
+            _ => None,
+        }
+    }
+
+    /// The letter of the file, 'a' to 'h'
+    pub const fn to_char(self) -> char {
+        (b'a' + self.index as u8) as char
+    }
 }
 
 impl fmt::Display for File {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", (b'a' + self.index as u8) as char)
+        write!(f, "{}", self.to_char())
     }
 }
 
@@ -241,6 +311,26 @@ impl Rank {
         Self { index }
     }
 
+    /// Mirror the rank, 1 to 8
+    pub const fn flip(self) -> Self {
+        Self {
+            index: 7 - self.index,
+        }
+    }
+
+    /// Parse a rank from its digit '1' to '8'
+    pub const fn from_char(c: char) -> Option<Self> {
+        match c {
+            '1'..='8' => Some(Self::new(c as u32 - '1' as u32)),
+            _ => None,
+        }
+    }
+
+    /// The digit of the rank, '1' to '8'
+    pub const fn to_char(self) -> char {
+        (b'1' + self.index as u8) as char
+    }
+
     pub fn last_pawn_rank(color: Color) -> Self {
         if color == Color::White {
             Self::SEVENTH
@@ -248,10 +338,128 @@ impl Rank {
             Self::SECOND
         }
     }
+
+    /// The rank pawns of the given color promote on, the 8th for White
+    pub fn promotion_rank(color: Color) -> Self {
+        if color == Color::White {
+            Self::EIGHTH
+        } else {
+            Self::FIRST
+        }
+    }
+
+    /// The rank pawns of the given color start on, the 2nd for White
+    pub fn pawn_start_rank(color: Color) -> Self {
+        Self::last_pawn_rank(!color)
+    }
+
+    /// The rank of the en passant square when the given color captures
+    /// en passant, the 6th for White
+    pub fn en_passant_rank(color: Color) -> Self {
+        if color == Color::White {
+            Self::SIXTH
+        } else {
+            Self::THIRD
+        }
+    }
 }
 
 impl fmt::Display for Rank {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.index + 1)
+        write!(f, "{}", self.to_char())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pawn_ranks() {
+        assert!(Rank::promotion_rank(Color::White) == Rank::EIGHTH);
+        assert!(Rank::promotion_rank(Color::Black) == Rank::FIRST);
+        assert!(Rank::pawn_start_rank(Color::White) == Rank::SECOND);
+        assert!(Rank::pawn_start_rank(Color::Black) == Rank::SEVENTH);
+        assert!(Rank::en_passant_rank(Color::White) == Rank::SIXTH);
+        assert!(Rank::en_passant_rank(Color::Black) == Rank::THIRD);
+        assert!(Rank::last_pawn_rank(Color::White) == Rank::SEVENTH);
+        assert!(Rank::last_pawn_rank(Color::Black) == Rank::SECOND);
+    }
+
+    #[test]
+    fn test_file_rank_chars() {
+        for (i, c) in ('a'..='h').enumerate() {
+            let file = File::from_char(c).unwrap();
+            assert!(file == File::new(i as u32));
+            assert_eq!(file.to_char(), c);
+            assert_eq!(file.to_string(), c.to_string());
+        }
+
+        for (i, c) in ('1'..='8').enumerate() {
+            let rank = Rank::from_char(c).unwrap();
+            assert!(rank == Rank::new(i as u32));
+            assert_eq!(rank.to_char(), c);
+            assert_eq!(rank.to_string(), c.to_string());
+        }
+
+        assert!(File::E.to_char() == 'e');
+        assert!(Rank::FOURTH.to_char() == '4');
+        assert!(File::from_char('i').is_none());
+        assert!(File::from_char('A').is_none());
+        assert!(Rank::from_char('0').is_none());
+        assert!(Rank::from_char('9').is_none());
+    }
+
+    #[test]
+    fn test_square_from_string() {
+        assert_eq!(Square::from_string("a1"), Some(Square::A1));
+        assert_eq!(Square::from_string("h8"), Some(Square::H8));
+        assert_eq!(Square::from_string("e4"), Some(Square::new(28)));
+        assert_eq!(Square::from_string("e"), None);
+        assert_eq!(Square::from_string("e44"), None);
+        assert_eq!(Square::from_string("i1"), None);
+    }
+
+    #[test]
+    fn test_square_distance() {
+        assert_eq!(Square::distance(Square::A1, Square::H8), 7);
+        assert_eq!(Square::distance(Square::A1, Square::A1), 0);
+        assert_eq!(Square::distance(Square::new(28), Square::new(44)), 2);
+        assert_eq!(Square::distance(Square::B1, Square::new(12)), 3);
+
+        assert_eq!(Square::manhattan_distance(Square::A1, Square::H8), 14);
+        assert_eq!(Square::manhattan_distance(Square::B1, Square::new(12)), 4);
+    }
+
+    #[test]
+    fn test_square_flips() {
+        assert_eq!(Square::A1.flip_file(), Square::H1);
+        assert_eq!(Square::A1.flip_rank(), Square::A8);
+        assert_eq!(Square::B1.flip_diag(), Square::new(8));
+        assert_eq!(Square::H8.flip_diag(), Square::H8);
+        assert_eq!(Square::new(28).flip_rank(), Square::new(36));
+        assert!(File::B.flip() == File::G);
+        assert!(Rank::FIRST.flip() == Rank::EIGHTH);
+
+        const FLIPPED: [Square; 64] = {
+            let mut table = [Square::NONE; 64];
+            let mut i = 0;
+            while i < 64 {
+                table[i] = Square::new(i as u32).flip_rank();
+                i += 1;
+            }
+            table
+        };
+
+        assert_eq!(FLIPPED[0], Square::A8);
+        assert_eq!(FLIPPED[63], Square::H1);
+    }
+
+    #[test]
+    fn test_square_is_none() {
+        assert!(Square::NONE.is_none());
+        assert!(!Square::NONE.is_some());
+        assert!(Square::A1.is_some());
+        assert!(Square::H8.is_some());
     }
 }
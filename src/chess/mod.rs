@@ -1,4 +1,5 @@
 mod hyperbola;
+mod zobrist;
 
 pub mod attacks;
 pub mod bitboard;
@@ -6,6 +7,12 @@ pub mod castling_rights;
 pub mod color;
 pub mod coords;
 pub mod r#move;
+pub mod movegen;
+pub mod perft;
+pub mod pgn;
 pub mod piece;
 pub mod piecetype;
 pub mod position;
+
+pub use perft::perft_divide_json;
+pub use position::game_result;
@@ -4,6 +4,7 @@ use crate::chess::{
     coords::{File, Rank, Square},
     piece::Piece,
     piecetype::PieceType,
+    position::Position,
 };
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -74,6 +75,22 @@ impl Move {
         self.move_type
     }
 
+    pub const fn is_normal(&self) -> bool {
+        matches!(self.move_type, MoveType::Normal)
+    }
+
+    pub const fn is_promotion(&self) -> bool {
+        matches!(self.move_type, MoveType::Promotion)
+    }
+
+    pub const fn is_castle(&self) -> bool {
+        matches!(self.move_type, MoveType::Castle)
+    }
+
+    pub const fn is_en_passant(&self) -> bool {
+        matches!(self.move_type, MoveType::EnPassant)
+    }
+
     /// Get the promoted piece, Piece::none(), if not a promotion
     pub const fn promoted_piece(&self) -> Piece {
         self.promoted_piece
@@ -154,8 +171,12 @@ impl Move {
         }
     }
 
-    /// Fromat the move as UCI
+    /// Fromat the move as UCI, the null move is formatted as `0000`
     pub fn as_uci(&self) -> String {
+        if *self == Self::null() {
+            return "0000".to_string();
+        }
+
         let mut uci = format!("{}{}", self.from, self.to);
 
         if self.move_type == MoveType::Promotion {
@@ -194,6 +215,192 @@ impl Move {
 
         uci
     }
+
+    /// Format the move in Standard Algebraic Notation (SAN),
+    /// the move must be legal in the given position.
+    /// The null move is formatted as `--`.
+    pub fn as_san(&self, pos: &Position) -> String {
+        if *self == Self::null() {
+            return "--".to_string();
+        }
+
+        let mut san = String::new();
+
+        if self.move_type == MoveType::Castle {
+            san.push_str(match self.castle_type() {
+                CastleType::Short => "O-O",
+                CastleType::Long => "O-O-O",
+            });
+        } else {
+            let pt = pos.piece_at(self.from).piece_type();
+            let is_capture =
+                self.move_type == MoveType::EnPassant || pos.piece_at(self.to) != Piece::none();
+
+            if pt == PieceType::Pawn {
+                if is_capture {
+                    san.push_str(&self.from.file().to_string());
+                }
+            } else {
+                san.push(piece_type_char(pt));
+
+                // other pieces of the same type which can reach the same square
+                let ambiguous = pos
+                    .legal_moves()
+                    .into_iter()
+                    .filter(|mv| {
+                        mv.to == self.to
+                            && mv.from != self.from
+                            && mv.move_type != MoveType::Castle
+                            && pos.piece_at(mv.from).piece_type() == pt
+                    })
+                    .map(|mv| mv.from)
+                    .collect::<Vec<_>>();
+
+                if !ambiguous.is_empty() {
+                    if ambiguous.iter().all(|sq| sq.file() != self.from.file()) {
+                        san.push_str(&self.from.file().to_string());
+                    } else if ambiguous.iter().all(|sq| sq.rank() != self.from.rank()) {
+                        san.push_str(&self.from.rank().to_string());
+                    } else {
+                        san.push_str(&self.from.to_string());
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+
+            san.push_str(&self.to.to_string());
+
+            if self.move_type == MoveType::Promotion {
+                san.push('=');
+                san.push(piece_type_char(self.promoted_piece.piece_type()));
+            }
+        }
+
+        let after = pos.after_move(*self);
+        if after.is_checked(after.side_to_move()) {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        san
+    }
+
+    /// Parse a move in UCI notation, e.g. `e2e4`, `e1g1` or `e7e8q`, for the given position.
+    /// `0000` is parsed as the null move.
+    /// Returns None if the move isn't legal in the position.
+    pub fn from_uci(pos: &Position, uci: &str) -> Option<Self> {
+        if uci == "0000" {
+            return Some(Self::null());
+        }
+
+        pos.legal_moves().into_iter().find(|mv| mv.as_uci() == uci)
+    }
+
+    /// Parse a move in Standard Algebraic Notation (SAN) for the given position.
+    /// Check and annotation suffixes (`+`, `#`, `!`, `?`) are ignored.
+    /// Returns None if the SAN is malformed or doesn't match exactly one legal move.
+    pub fn from_san(pos: &Position, san: &str) -> Option<Self> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal_moves = pos.legal_moves();
+
+        let castle_type = match san {
+            "O-O" | "0-0" => Some(CastleType::Short),
+            "O-O-O" | "0-0-0" => Some(CastleType::Long),
+            _ => None,
+        };
+
+        if let Some(castle_type) = castle_type {
+            return legal_moves
+                .into_iter()
+                .find(|mv| mv.move_type == MoveType::Castle && mv.castle_type() == castle_type);
+        }
+
+        let mut chars = san.chars().collect::<Vec<_>>();
+
+        let pt = match chars.first() {
+            Some('N') => PieceType::Knight,
+            Some('B') => PieceType::Bishop,
+            Some('R') => PieceType::Rook,
+            Some('Q') => PieceType::Queen,
+            Some('K') => PieceType::King,
+            _ => PieceType::Pawn,
+        };
+
+        if pt != PieceType::Pawn {
+            chars.remove(0);
+        }
+
+        // promotion suffix, with or without '='
+        let mut promotion = PieceType::None;
+        if let Some(&c) = chars.last() {
+            if matches!(c, 'N' | 'B' | 'R' | 'Q') {
+                promotion = match c {
+                    'N' => PieceType::Knight,
+                    'B' => PieceType::Bishop,
+                    'R' => PieceType::Rook,
+                    _ => PieceType::Queen,
+                };
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        chars.retain(|&c| c != 'x' && c != '-');
+
+        if chars.len() < 2 {
+            return None;
+        }
+
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Square::from_string(&to)?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+
+        for c in chars {
+            match c {
+                'a'..='h' => from_file = Some(File::new(c as u32 - 'a' as u32)),
+                '1'..='8' => from_rank = Some(Rank::new(c as u32 - '1' as u32)),
+                _ => return None,
+            }
+        }
+
+        let mut candidates = legal_moves.into_iter().filter(|mv| {
+            mv.move_type != MoveType::Castle
+                && mv.to == to
+                && pos.piece_at(mv.from).piece_type() == pt
+                && mv.promoted_piece.piece_type() == promotion
+                && from_file.is_none_or(|file| mv.from.file() == file)
+                && from_rank.is_none_or(|rank| mv.from.rank() == rank)
+        });
+
+        let mv = candidates.next()?;
+
+        if candidates.next().is_some() {
+            return None;
+        }
+
+        Some(mv)
+    }
+}
+
+fn piece_type_char(pt: PieceType) -> char {
+    match pt {
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+        _ => panic!("Invalid piece type"),
+    }
 }
 
 impl Default for Move {
@@ -201,3 +408,99 @@ impl Default for Move {
         Self::null()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_san_roundtrip() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        for mv in pos.legal_moves() {
+            let san = mv.as_san(&pos);
+            assert_eq!(Move::from_san(&pos, &san), Some(mv), "san {}", san);
+        }
+    }
+
+    #[test]
+    fn test_uci_roundtrip() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        for mv in pos.legal_moves() {
+            assert_eq!(Move::from_uci(&pos, &mv.as_uci()), Some(mv));
+        }
+
+        assert_eq!(
+            Move::from_uci(&pos, "e1g1"),
+            Some(Move::castle(Square::E1, Square::H1))
+        );
+        assert_eq!(Move::from_uci(&pos, "e1e3"), None);
+        assert_eq!(Move::from_uci(&pos, "0000"), Some(Move::null()));
+    }
+
+    #[test]
+    fn test_move_type_predicates() {
+        let normal = Move::normal(Square::new(12), Square::new(28));
+        assert!(normal.is_normal());
+        assert!(!normal.is_promotion() && !normal.is_castle() && !normal.is_en_passant());
+
+        let promotion = Move::promotion(
+            Square::new(52),
+            Square::new(60),
+            Piece::new(PieceType::Queen, Color::White),
+        );
+        assert!(promotion.is_promotion());
+        assert!(!promotion.is_normal() && !promotion.is_castle() && !promotion.is_en_passant());
+
+        let castle = Move::from_castle(CastleType::Short, Color::Black);
+        assert!(castle.is_castle());
+        assert!(!castle.is_normal() && !castle.is_promotion() && !castle.is_en_passant());
+
+        let en_passant = Move::en_passant(Square::new(36), Square::new(43));
+        assert!(en_passant.is_en_passant());
+        assert!(!en_passant.is_normal() && !en_passant.is_promotion() && !en_passant.is_castle());
+    }
+
+    #[test]
+    fn test_san_format() {
+        let pos = Position::new();
+        let mv = Move::normal(Square::new(6), Square::new(21));
+        assert_eq!(mv.as_san(&pos), "Nf3");
+        assert_eq!(
+            Move::from_san(&pos, "e4"),
+            Some(Move::normal(Square::new(12), Square::new(28)))
+        );
+        assert_eq!(Move::from_san(&pos, "e5"), None);
+
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        assert_eq!(Move::castle(Square::E1, Square::A1).as_san(&pos), "O-O-O");
+        assert_eq!(
+            Move::from_san(&pos, "O-O"),
+            Some(Move::castle(Square::E1, Square::H1))
+        );
+        assert_eq!(Move::normal(Square::A1, Square::A8).as_san(&pos), "Ra8+");
+
+        let pos = Position::from_fen("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1").unwrap();
+        assert_eq!(Move::normal(Square::A1, Square::A8).as_san(&pos), "Ra8#");
+        assert_eq!(Move::normal(Square::A1, Square::D1).as_san(&pos), "Rad1");
+        assert_eq!(Move::from_san(&pos, "Rd1"), None);
+    }
+
+    #[test]
+    fn test_null_move_format() {
+        let pos = Position::new();
+        assert_eq!(Move::null().as_uci(), "0000");
+        assert_eq!(Move::null().as_san(&pos), "--");
+        assert_eq!(
+            Move::from_uci(&pos, &Move::null().as_uci()),
+            Some(Move::null())
+        );
+    }
+}
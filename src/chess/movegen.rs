@@ -0,0 +1,104 @@
+use arrayvec::ArrayVec;
+
+use crate::chess::{attacks, position::Position, r#move::Move};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Start,
+    Captures,
+    Quiets,
+    Done,
+}
+
+/// Lazy generator of the legal moves of a position,
+/// yields all captures and promotions (including en passant) before the quiet moves.
+///
+/// Each stage is generated when the previous one is exhausted, so the quiet moves
+/// are never generated if iteration stops during the captures. The legality of
+/// each move is only checked when it is about to be yielded.
+#[derive(Debug, Clone)]
+pub struct MoveGen<'a> {
+    pos: &'a Position,
+    moves: ArrayVec<Move, 256>,
+    stage: Stage,
+    index: usize,
+}
+
+impl<'a> MoveGen<'a> {
+    pub fn new(pos: &'a Position) -> Self {
+        Self {
+            pos,
+            moves: ArrayVec::new(),
+            stage: Stage::Start,
+            index: 0,
+        }
+    }
+}
+
+impl Iterator for MoveGen<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        let pos = self.pos;
+
+        loop {
+            if self.index == self.moves.len() {
+                (self.stage, self.moves) = match self.stage {
+                    Stage::Start => (Stage::Captures, attacks::capture_moves(pos)),
+                    Stage::Captures => (Stage::Quiets, attacks::quiet_moves(pos)),
+                    Stage::Quiets | Stage::Done => (Stage::Done, ArrayVec::new()),
+                };
+                self.index = 0;
+
+                if self.stage == Stage::Done {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let mv = self.moves[self.index];
+            self.index += 1;
+
+            if !pos.after_move(mv).is_checked(pos.side_to_move()) {
+                return Some(mv);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_generator() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+        ];
+
+        for fen in fens {
+            let pos = Position::from_fen(fen).unwrap();
+            let generated: Vec<Move> = pos.move_generator().collect();
+
+            let mut sorted: Vec<String> = generated.iter().map(|mv| mv.as_uci()).collect();
+            let mut expected: Vec<String> =
+                pos.legal_moves().iter().map(|mv| mv.as_uci()).collect();
+            sorted.sort();
+            expected.sort();
+            assert_eq!(sorted, expected, "{fen}");
+
+            // all captures and promotions come before the first quiet move
+            let captures: Vec<bool> = generated
+                .iter()
+                .map(|mv| mv.is_promotion() || pos.captured_piece(*mv).is_some())
+                .collect();
+            let first_quiet = captures.iter().position(|c| !c).unwrap_or(captures.len());
+            assert!(captures[first_quiet..].iter().all(|c| !c), "{fen}");
+        }
+    }
+}
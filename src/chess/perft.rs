@@ -0,0 +1,92 @@
+use crate::chess::{position::Position, r#move::Move};
+
+/// Count the leaf nodes of the legal move tree up to the given depth.
+pub fn perft(pos: &Position, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = pos.legal_moves();
+
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .iter()
+        .map(|mv| perft(&pos.after_move(*mv), depth - 1))
+        .sum()
+}
+
+/// Count the leaf nodes below each legal move, sorted by the UCI notation of the move.
+/// The counts sum up to `perft(pos, depth)`, a depth of 0 returns no moves.
+pub fn perft_divide(pos: &Position, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut divide: Vec<(Move, u64)> = pos
+        .legal_moves()
+        .iter()
+        .map(|mv| (*mv, perft(&pos.after_move(*mv), depth - 1)))
+        .collect();
+
+    divide.sort_by_cached_key(|(mv, _)| mv.as_uci());
+    divide
+}
+
+/// Same as [`perft_divide`] as a JSON object, e.g. `{"a2a3": 380, "a2a4": 420, ...}`,
+/// to diff against the `go perft` output of other engines.
+pub fn perft_divide_json(pos: &Position, depth: u32) -> String {
+    let fields: Vec<String> = perft_divide(pos, depth)
+        .iter()
+        .map(|(mv, nodes)| format!("\"{}\": {}", mv.as_uci(), nodes))
+        .collect();
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perft_divide_json() {
+        let json = perft_divide_json(&Position::new(), 2);
+
+        assert!(json.starts_with("{\"a2a3\": 20, \"a2a4\": 20, "));
+        assert!(json.ends_with("\"h2h4\": 20}"));
+
+        let fields: Vec<&str> = json
+            .trim_start_matches('{')
+            .trim_end_matches('}')
+            .split(", ")
+            .collect();
+        assert_eq!(fields.len(), 20);
+
+        let total: u64 = fields
+            .iter()
+            .map(|field| field.split(": ").nth(1).unwrap().parse::<u64>().unwrap())
+            .sum();
+        assert_eq!(total, 400);
+
+        assert_eq!(perft_divide_json(&Position::new(), 0), "{}");
+    }
+
+    #[test]
+    fn test_perft() {
+        // kiwipete
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        assert_eq!(perft(&pos, 1), 48);
+        assert_eq!(perft(&pos, 2), 2039);
+        assert_eq!(perft(&pos, 3), 97862);
+        assert_eq!(
+            perft_divide(&pos, 2).iter().map(|(_, n)| n).sum::<u64>(),
+            2039
+        );
+    }
+}
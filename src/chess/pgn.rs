@@ -0,0 +1,267 @@
+use thiserror::Error;
+
+use crate::chess::{color::Color, position::Position, r#move::Move};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PgnError {
+    #[error("Invalid FEN tag: {0}")]
+    InvalidFen(String),
+    #[error("Illegal or ambiguous move `{0}`")]
+    IllegalMove(String),
+    #[error("Unterminated {0}")]
+    Unterminated(&'static str),
+}
+
+type Result<T> = std::result::Result<T, PgnError>;
+
+/// A single game parsed from a PGN file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    /// The tag pairs in the order they appear, e.g. ("White", "Carlsen").
+    pub tags: Vec<(String, String)>,
+    /// The start position, taken from the FEN tag if present.
+    pub start: Position,
+    /// The moves of the main line, variations are skipped.
+    pub moves: Vec<Move>,
+    /// The game result from White's point of view,
+    /// 1, 0, -1 for win, draw, loss or None if unknown ("*").
+    pub result: Option<i16>,
+}
+
+impl PgnGame {
+    fn new() -> Self {
+        Self {
+            tags: Vec::new(),
+            start: Position::new(),
+            moves: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Returns the value of the given tag, if present
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse all games of a PGN file.
+///
+/// Comments, NAGs and variations are skipped, only the main line is kept.
+/// A game ends with its result token ("1-0", "0-1", "1/2-1/2" or "*").
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::new();
+    let mut pos = game.start;
+    let mut has_content = false;
+
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' => {
+                chars.next();
+                let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let (key, value) = tag
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((&tag, ""));
+                let value = value.trim().trim_matches('"').to_string();
+
+                if key == "FEN" {
+                    game.start = Position::from_fen(&value)
+                        .map_err(|_| PgnError::InvalidFen(value.clone()))?;
+                    pos = game.start;
+                }
+
+                game.tags.push((key.to_string(), value));
+                has_content = true;
+            }
+            '{' => {
+                chars.next();
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err(PgnError::Unterminated("comment"));
+                }
+            }
+            ';' => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+            }
+            '(' => {
+                chars.next();
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('(') => depth += 1,
+                        Some(')') => depth -= 1,
+                        Some('{') => {
+                            if !chars.by_ref().any(|c| c == '}') {
+                                return Err(PgnError::Unterminated("comment"));
+                            }
+                        }
+                        Some(_) => {}
+                        None => return Err(PgnError::Unterminated("variation")),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '{' | '(' | ';' | '[') {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+
+                let result = match token.as_str() {
+                    "1-0" => Some(Some(1)),
+                    "0-1" => Some(Some(-1)),
+                    "1/2-1/2" => Some(Some(0)),
+                    "*" => Some(None),
+                    _ => None,
+                };
+
+                if let Some(result) = result {
+                    game.result = result;
+                    games.push(game);
+                    game = PgnGame::new();
+                    pos = game.start;
+                    has_content = false;
+                    continue;
+                }
+
+                // move numbers such as "12." or "12..." may be attached to the move
+                let san = strip_move_number(&token);
+
+                if san.is_empty() || san.starts_with('$') {
+                    continue;
+                }
+
+                let mv = Move::from_san(&pos, san)
+                    .ok_or_else(|| PgnError::IllegalMove(san.to_string()))?;
+
+                pos.do_move(mv);
+                game.moves.push(mv);
+                has_content = true;
+            }
+        }
+    }
+
+    // a trailing game without a result token
+    if has_content {
+        games.push(game);
+    }
+
+    Ok(games)
+}
+
+// Strip a move number such as "12." or "12..." from the start of the token.
+// Only digits followed by a dot are a move number, "0-0" is castling.
+fn strip_move_number(token: &str) -> &str {
+    let rest = token.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    if rest.len() < token.len() && rest.starts_with('.') {
+        rest.trim_start_matches('.')
+    } else {
+        token
+    }
+}
+
+/// Render the moves as PGN movetext in SAN, e.g. `1. e4 e5 2. Nf3`.
+///
+/// Move numbers continue from the start position, a line starting
+/// with Black to move begins with `1...`. No result token is appended.
+pub fn to_san_line(start: &Position, moves: &[Move]) -> String {
+    let mut pos = *start;
+    let mut line = String::new();
+
+    for (i, mv) in moves.iter().enumerate() {
+        let move_number = pos.ply() / 2 + 1;
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        if pos.side_to_move() == Color::White {
+            line.push_str(&format!("{}. ", move_number));
+        } else if i == 0 {
+            line.push_str(&format!("{}... ", move_number));
+        }
+
+        line.push_str(&mv.as_san(&pos));
+        pos.do_move(*mv);
+    }
+
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sample() {
+        let games = parse_pgn(include_str!("../../test/sample.pgn")).unwrap();
+
+        assert_eq!(games.len(), 2);
+
+        assert_eq!(games[0].tag("White"), Some("Scholar"));
+        assert_eq!(games[0].moves.len(), 7);
+        assert_eq!(games[0].result, Some(1));
+
+        let mut pos = games[0].start;
+        for mv in &games[0].moves {
+            pos.do_move(*mv);
+        }
+        assert!(pos.legal_moves().is_empty());
+
+        assert_eq!(
+            games[1].start.fen().unwrap(),
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"
+        );
+        assert_eq!(games[1].moves.len(), 4);
+        assert_eq!(games[1].result, Some(0));
+    }
+
+    #[test]
+    fn test_to_san_line() {
+        let games = parse_pgn(include_str!("../../test/sample.pgn")).unwrap();
+
+        assert_eq!(
+            to_san_line(&games[0].start, &games[0].moves),
+            "1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7#"
+        );
+
+        let mut start = games[0].start;
+        start.do_move(games[0].moves[0]);
+        assert_eq!(to_san_line(&start, &games[0].moves[1..3]), "1... e5 2. Bc4");
+
+        assert_eq!(to_san_line(&start, &[]), "");
+    }
+
+    #[test]
+    fn test_zero_castling() {
+        let games =
+            parse_pgn("[FEN \"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1\"]\n\n1.0-0 0-0-0 2. Kg2 *")
+                .unwrap();
+
+        assert_eq!(games.len(), 1);
+        assert_eq!(
+            to_san_line(&games[0].start, &games[0].moves),
+            "1. O-O O-O-O 2. Kg2"
+        );
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        assert_eq!(
+            parse_pgn("1. e4 e5 2. Ke3 *"),
+            Err(PgnError::IllegalMove("Ke3".to_string()))
+        );
+    }
+}
@@ -40,6 +40,18 @@ impl Piece {
         }
     }
 
+    /// Returns true if this is `Piece::none()`, i.e. an empty square
+    #[inline(always)]
+    pub const fn is_none(&self) -> bool {
+        self.id == Self::NONE.id
+    }
+
+    /// Returns true if this is an actual piece
+    #[inline(always)]
+    pub const fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
     /// Get the piece type of the piece
     #[inline(always)]
     pub const fn piece_type(&self) -> PieceType {
@@ -60,4 +72,80 @@ impl Piece {
     pub fn id(&self) -> u8 {
         self.id
     }
+
+    /// Get the FEN letter of the piece, uppercase for White, `.` for `Piece::none()`
+    pub fn to_char(&self) -> char {
+        if self.is_none() {
+            return '.';
+        }
+
+        let c = match self.piece_type() {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k',
+            PieceType::None => '?',
+        };
+
+        if self.color() == Color::White {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
+    }
+
+    /// Get the unicode chess symbol of the piece, e.g. ♘ for a white knight,
+    /// `.` for `Piece::none()`
+    pub fn to_unicode(&self) -> char {
+        if self.is_none() {
+            return '.';
+        }
+
+        match (self.piece_type(), self.color()) {
+            (PieceType::Pawn, Color::White) => '♙',
+            (PieceType::Knight, Color::White) => '♘',
+            (PieceType::Bishop, Color::White) => '♗',
+            (PieceType::Rook, Color::White) => '♖',
+            (PieceType::Queen, Color::White) => '♕',
+            (PieceType::King, Color::White) => '♔',
+            (PieceType::Pawn, Color::Black) => '♟',
+            (PieceType::Knight, Color::Black) => '♞',
+            (PieceType::Bishop, Color::Black) => '♝',
+            (PieceType::Rook, Color::Black) => '♜',
+            (PieceType::Queen, Color::Black) => '♛',
+            (PieceType::King, Color::Black) => '♚',
+            (PieceType::None, _) => '?',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piece_is_none() {
+        assert!(Piece::none().is_none());
+        assert!(!Piece::NONE.is_some());
+        assert!(Piece::WHITE_PAWN.is_some());
+        assert!(Piece::BLACK_KING.is_some());
+    }
+
+    #[test]
+    fn test_to_char() {
+        assert_eq!(Piece::WHITE_KNIGHT.to_char(), 'N');
+        assert_eq!(Piece::BLACK_QUEEN.to_char(), 'q');
+        assert_eq!(Piece::none().to_char(), '.');
+    }
+
+    #[test]
+    fn test_to_unicode() {
+        assert_eq!(Piece::WHITE_KNIGHT.to_unicode(), '♘');
+        assert_eq!(Piece::BLACK_KNIGHT.to_unicode(), '♞');
+        assert_eq!(Piece::WHITE_KING.to_unicode(), '♔');
+        assert_eq!(Piece::BLACK_PAWN.to_unicode(), '♟');
+        assert_eq!(Piece::none().to_unicode(), '.');
+    }
 }
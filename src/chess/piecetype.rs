@@ -1,4 +1,4 @@
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -33,3 +33,42 @@ impl PieceType {
         *self as u8
     }
 }
+
+/// Centipawn values of the piece types, the king has no value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceValues {
+    pub pawn: i32,
+    pub knight: i32,
+    pub bishop: i32,
+    pub rook: i32,
+    pub queen: i32,
+}
+
+impl PieceValues {
+    /// The classical values 100, 300, 300, 500, 900
+    pub const CLASSICAL: Self = Self {
+        pawn: 100,
+        knight: 300,
+        bishop: 300,
+        rook: 500,
+        queen: 900,
+    };
+
+    /// Returns the value of the given piece type, 0 for the king and None
+    pub const fn value(&self, pt: PieceType) -> i32 {
+        match pt {
+            PieceType::Pawn => self.pawn,
+            PieceType::Knight => self.knight,
+            PieceType::Bishop => self.bishop,
+            PieceType::Rook => self.rook,
+            PieceType::Queen => self.queen,
+            PieceType::King | PieceType::None => 0,
+        }
+    }
+}
+
+impl Default for PieceValues {
+    fn default() -> Self {
+        Self::CLASSICAL
+    }
+}
@@ -1,12 +1,18 @@
+use arrayvec::ArrayVec;
+
+use crate::common::compressed_position::CompressedPosition;
+
 use crate::chess::{
     attacks,
     bitboard::Bitboard,
-    castling_rights::{CastleType, CastlingRights},
+    castling_rights::{CastleType, CastlingRights, CastlingTraits},
     color::Color,
     coords::Square,
+    movegen::MoveGen,
     piece::Piece,
-    piecetype::PieceType,
+    piecetype::{PieceType, PieceValues},
     r#move::{Move, MoveType},
+    zobrist::{ZobristKeys, KEYS, KEYS_HIGH},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -29,9 +35,61 @@ pub struct Position {
     enpassant: Square,
 }
 
+/// Checkers and pinned pieces of the side to move, see [`Position::check_info`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheckInfo {
+    /// Enemy pieces giving check
+    pub checkers: Bitboard,
+    /// Pieces of the side to move pinned to their king
+    pub pinned: Bitboard,
+    /// The squares between the king and each pinning piece, including the pinner
+    pub pin_rays: Bitboard,
+}
+
+/// State lost by [`Position::make`] that [`Position::unmake`] needs to restore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UndoInfo {
+    captured: Piece,
+    castling_rights: CastlingRights,
+    enpassant: Square,
+    halfm: u8,
+    fullm: u16,
+}
+
+/// The notation of the castling field of a FEN
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FenStyle {
+    /// `KQkq`
+    #[default]
+    Standard,
+    /// Shredder-FEN, the files of the castling rooks, e.g. `HAha`
+    Shredder,
+}
+
+/// How pieces are drawn by [`Position::to_ascii_with`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BoardStyle {
+    /// FEN letters, uppercase for White
+    #[default]
+    Letters,
+    /// Unicode chess symbols, e.g. ♘
+    Unicode,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PositionError {
     InvalidFEN,
+    IllegalMove,
+    /// A side doesn't have exactly one king
+    InvalidKingCount,
+    /// A pawn stands on the first or eighth rank
+    PawnOnBackRank,
+    /// The side that just moved is in check
+    OpponentInCheck,
+    /// No pawn can just have double pushed past the en passant square
+    InvalidEnPassant,
+    /// A castling right without the king and rook on their back rank
+    InvalidCastlingRights,
 }
 
 type Result<T> = std::result::Result<T, PositionError>;
@@ -97,6 +155,108 @@ impl Position {
         }
     }
 
+    /// Create a position from the piece on each square, indexed from a1 to h8.
+    /// The position has no castling rights or en passant square.
+    pub fn from_board_array(squares: &[Option<Piece>; 64], stm: Color) -> Result<Self> {
+        let mut pos = Self::empty();
+        pos.stm = stm;
+
+        for (index, piece) in squares.iter().enumerate() {
+            if let Some(piece) = piece.filter(|p| p.is_some()) {
+                pos.place(piece, Square::new(index as u32));
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            if pos.pieces_bb_color(color, PieceType::King).count() != 1 {
+                return Err(PositionError::InvalidKingCount);
+            }
+        }
+
+        Ok(pos)
+    }
+
+    /// Check that the position could occur in a game: one king per side, no pawns
+    /// on the back ranks, the side not to move isn't in check, the en passant square
+    /// follows a double push and castling rights have their king and rook in place.
+    pub fn validate(&self) -> Result<()> {
+        for color in [Color::White, Color::Black] {
+            if self.pieces_bb_color(color, PieceType::King).count() != 1 {
+                return Err(PositionError::InvalidKingCount);
+            }
+        }
+
+        let back_ranks = Bitboard::from_rank(0) | Bitboard::from_rank(7);
+        if (self.pieces_bb_type(PieceType::Pawn) & back_ranks).bits() != 0 {
+            return Err(PositionError::PawnOnBackRank);
+        }
+
+        if self.is_checked(!self.stm) {
+            return Err(PositionError::OpponentInCheck);
+        }
+
+        if self.enpassant.is_some() {
+            let ep_rank = if self.stm == Color::White { 5 } else { 2 };
+
+            if self.enpassant.index() / 8 != ep_rank {
+                return Err(PositionError::InvalidEnPassant);
+            }
+
+            let behind = if self.stm == Color::White {
+                self.enpassant.index() + 8
+            } else {
+                self.enpassant.index() - 8
+            };
+            let pushed = Square::new(self.enpassant.index() ^ 8);
+
+            if self.piece_at(self.enpassant).is_some()
+                || self.piece_at(Square::new(behind)).is_some()
+                || self.piece_at(pushed) != Piece::new(PieceType::Pawn, !self.stm)
+            {
+                return Err(PositionError::InvalidEnPassant);
+            }
+        }
+
+        for (right, color, castle_type) in [
+            (
+                CastlingRights::WHITE_KING_SIDE,
+                Color::White,
+                CastleType::Short,
+            ),
+            (
+                CastlingRights::WHITE_QUEEN_SIDE,
+                Color::White,
+                CastleType::Long,
+            ),
+            (
+                CastlingRights::BLACK_KING_SIDE,
+                Color::Black,
+                CastleType::Short,
+            ),
+            (
+                CastlingRights::BLACK_QUEEN_SIDE,
+                Color::Black,
+                CastleType::Long,
+            ),
+        ] {
+            if !self.castling_rights.contains(right) {
+                continue;
+            }
+
+            let back_rank = if color == Color::White { 0 } else { 7 };
+            let rook_sq =
+                Square::new(back_rank * 8 + self.castling_rook_file(color, castle_type) as u32);
+
+            if self.king_sq(color).index() / 8 != back_rank
+                || self.piece_at(rook_sq) != Piece::new(PieceType::Rook, color)
+            {
+                return Err(PositionError::InvalidCastlingRights);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the current side to move's color
     pub fn side_to_move(&self) -> Color {
         self.stm
@@ -107,6 +267,22 @@ impl Position {
         Bitboard::new(self.bb_color[0] | self.bb_color[1])
     }
 
+    /// Returns a bitboard with all empty squares.
+    /// Named `empty_squares` since `Position::empty` creates an empty board.
+    pub fn empty_squares(&self) -> Bitboard {
+        !self.occupied()
+    }
+
+    /// Returns the bitboard of all pieces of the side to move
+    pub fn us(&self) -> Bitboard {
+        Bitboard::new(self.bb_color[self.stm as usize])
+    }
+
+    /// Returns the bitboard of all pieces of the opponent of the side to move
+    pub fn them(&self) -> Bitboard {
+        Bitboard::new(self.bb_color[!self.stm as usize])
+    }
+
     /// Returns the bitboard of all pieces of a given color
     pub fn pieces_bb(&self, color: Color) -> Bitboard {
         let bb = Bitboard::new(self.bb_color[color as usize]);
@@ -122,6 +298,13 @@ impl Position {
         Bitboard::new(self.bb[pt.ordinal() as usize])
     }
 
+    /// Returns the pieces of any of the given types, of both colors
+    pub fn pieces_of_types(&self, types: &[PieceType]) -> Bitboard {
+        types
+            .iter()
+            .fold(Bitboard::new(0), |bb, &pt| bb | self.pieces_bb_type(pt))
+    }
+
     /// Returns the bitboard of all pieces of a given color and piece type
     pub fn pieces_bb_color(&self, color: Color, pt: PieceType) -> Bitboard {
         Bitboard::new(self.bb_color[color as usize] & self.bb[pt.ordinal() as usize])
@@ -129,7 +312,7 @@ impl Position {
 
     /// Returns the piece at a given square, Piece::NONE if the square is empty
     pub fn piece_at(&self, square: Square) -> Piece {
-        debug_assert!(square != Square::NONE);
+        debug_assert!(square.is_some());
 
         self.pieces[square.index() as usize]
     }
@@ -144,6 +327,19 @@ impl Position {
         self.enpassant
     }
 
+    /// Returns the piece captured by the move without playing it,
+    /// the pawn behind the target square for en passant and None for castling
+    /// and quiet moves.
+    pub fn captured_piece(&self, mv: Move) -> Option<Piece> {
+        let captured = match mv.mtype() {
+            MoveType::Castle => return None,
+            MoveType::EnPassant => self.piece_at(Square::new(mv.to().index() ^ 8)),
+            _ => self.piece_at(mv.to()),
+        };
+
+        captured.is_some().then_some(captured)
+    }
+
     /// Make a legal move on the board
     pub fn do_move(&mut self, mv: Move) {
         debug_assert!(self.bb[PieceType::King.ordinal() as usize].count_ones() == 2);
@@ -153,17 +349,16 @@ impl Position {
         let piece = self.piece_at(from);
         let pt = piece.piece_type();
 
-        debug_assert!(from != Square::NONE);
-        debug_assert!(to != Square::NONE);
-        debug_assert!(piece != Piece::none());
+        debug_assert!(from.is_some());
+        debug_assert!(to.is_some());
+        debug_assert!(piece.is_some());
 
         // clear piece from start
         self.remove_piecetype(self.stm, pt, from);
 
-        // capture piece
-        if mv.mtype() != MoveType::Castle {
-            let captured = self.piece_at(to);
-            if captured != Piece::none() {
+        // capture piece, the en passant pawn is removed below
+        if !mv.is_en_passant() {
+            if let Some(captured) = self.captured_piece(mv) {
                 let cap_pt = captured.piece_type();
                 self.remove_piecetype(!self.stm, cap_pt, to);
 
@@ -179,18 +374,18 @@ impl Position {
             self.update_castling_rights_color(self.stm, from, to);
         }
 
-        if mv.mtype() == MoveType::Promotion {
+        if mv.is_promotion() {
             let promotion = mv.promoted_piece();
             self.place_piece(self.stm, promotion, to);
-        } else if mv.mtype() == MoveType::EnPassant {
+        } else if mv.is_en_passant() {
             debug_assert!(piece.piece_type() == PieceType::Pawn);
 
             let captured_sq = Square::new(to.index() ^ 8);
             self.remove_piecetype(!self.stm, PieceType::Pawn, captured_sq);
             self.place_piece(self.stm, piece, to);
-        } else if mv.mtype() == MoveType::Normal {
+        } else if mv.is_normal() {
             self.place_piece(self.stm, piece, to);
-        } else if mv.mtype() == MoveType::Castle {
+        } else if mv.is_castle() {
             if mv.castle_type() == CastleType::Short {
                 let rook_to = if self.stm == Color::White {
                     Square::F1
@@ -319,6 +514,15 @@ impl Position {
         self.stm = side;
     }
 
+    /// Returns a copy of the position with the given side to move and nothing else changed,
+    /// unlike a null move the en passant square and the counters are kept.
+    /// The result may be illegal, e.g. if the side not to move is in check.
+    pub fn with_side_to_move(&self, c: Color) -> Position {
+        let mut pos = *self;
+        pos.stm = c;
+        pos
+    }
+
     pub fn set_ply(&mut self, ply: u16) {
         self.fullm = (ply / 2) + 1;
     }
@@ -335,11 +539,77 @@ impl Position {
         self.halfm as u16
     }
 
+    /// Returns true if a draw can be claimed under the fifty-move rule,
+    /// i.e. 100 plies without a capture or pawn move
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.rule50_counter() >= 100
+    }
+
+    /// Returns true if the game is drawn automatically under the seventy-five-move rule,
+    /// i.e. 150 plies without a capture or pawn move
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.rule50_counter() >= 150
+    }
+
+    /// Returns true if the side to move is checkmated
+    pub fn is_checkmate(&self) -> bool {
+        self.checkers().bits() != 0 && self.legal_moves().is_empty()
+    }
+
+    /// Returns true if the side to move has no legal move but isn't in check
+    pub fn is_stalemate(&self) -> bool {
+        self.checkers().bits() == 0 && self.legal_moves().is_empty()
+    }
+
+    /// Returns true if neither side can checkmate, i.e. only kings are left
+    /// besides a single knight or bishop, or bishops which all stand on
+    /// squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = self.pieces_bb_type(PieceType::Pawn)
+            | self.pieces_bb_type(PieceType::Rook)
+            | self.pieces_bb_type(PieceType::Queen);
+
+        if heavy.bits() != 0 {
+            return false;
+        }
+
+        let knights = self.pieces_bb_type(PieceType::Knight).count();
+        let bishops = self.pieces_bb_type(PieceType::Bishop);
+
+        if knights + bishops.count() <= 1 {
+            return true;
+        }
+
+        const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+        knights == 0 && (bishops.bits() & DARK_SQUARES == 0 || bishops.bits() & !DARK_SQUARES == 0)
+    }
+
+    /// Returns true if mate can't be forced against correct defence: the material
+    /// is insufficient or two knights face a bare king (KNNvK). Unlike
+    /// [`Self::is_insufficient_material`] KNNvK still allows a helpmate,
+    /// so such positions aren't dead draws.
+    pub fn cannot_force_mate(&self) -> bool {
+        if self.is_insufficient_material() {
+            return true;
+        }
+
+        let knights = self.pieces_bb_type(PieceType::Knight);
+        let kings = self.pieces_bb_type(PieceType::King);
+
+        // only kings and two knights, the other side has a bare king
+        (self.occupied() & !kings) == knights
+            && knights.count() == 2
+            && [Color::White, Color::Black]
+                .into_iter()
+                .any(|c| self.pieces_bb(c).count() == 1)
+    }
+
     /// Places a piece on the board
     #[inline(always)]
     pub fn place(&mut self, pc: Piece, sq: Square) {
-        debug_assert!(pc != Piece::none());
-        debug_assert!(sq != Square::NONE);
+        debug_assert!(pc.is_some());
+        debug_assert!(sq.is_some());
 
         self.place_piece(pc.color(), pc, sq);
     }
@@ -347,8 +617,8 @@ impl Position {
     /// Places a piece on the board
     #[inline(always)]
     fn place_piece(&mut self, side: Color, pc: Piece, sq: Square) {
-        debug_assert!(pc != Piece::none());
-        debug_assert!(sq != Square::NONE);
+        debug_assert!(pc.is_some());
+        debug_assert!(sq.is_some());
         debug_assert!(side == pc.color());
 
         let mask = 1u64 << (sq.index());
@@ -361,8 +631,8 @@ impl Position {
     #[inline(always)]
     #[allow(dead_code)]
     fn remove_piece(&mut self, side: Color, pc: Piece, sq: Square) {
-        debug_assert!(pc != Piece::none());
-        debug_assert!(sq != Square::NONE);
+        debug_assert!(pc.is_some());
+        debug_assert!(sq.is_some());
 
         let mask = 1u64 << (sq.index());
         self.bb_color[side as usize] ^= mask;
@@ -373,7 +643,7 @@ impl Position {
     #[inline(always)]
     fn remove_piecetype(&mut self, side: Color, pt: PieceType, sq: Square) {
         debug_assert!(pt != PieceType::None);
-        debug_assert!(sq != Square::NONE);
+        debug_assert!(sq.is_some());
 
         let mask = 1u64 << (sq.index());
         self.bb_color[side as usize] ^= mask;
@@ -383,9 +653,84 @@ impl Position {
 
     /// Returns the FEN representation of the position
     pub fn fen(&self) -> Result<String> {
+        self.fen_with(FenStyle::Standard)
+    }
+
+    /// Returns the piece placement, the first field of the FEN
+    pub fn board_fen(&self) -> Result<String> {
         let mut fen = String::new();
+        self.write_board_fen(&mut fen)?;
+        Ok(fen)
+    }
 
-        // pieces
+    /// Returns the first four fields of the FEN, without the move counters
+    pub fn position_fen(&self) -> Result<String> {
+        self.position_fen_with(FenStyle::Standard)
+    }
+
+    /// Render the board for humans, White at the bottom with rank numbers
+    /// and file letters, followed by the side to move, castling rights and
+    /// en passant square. Empty squares are shown as `.`.
+    pub fn to_ascii(&self) -> String {
+        self.to_ascii_with(BoardStyle::Letters)
+    }
+
+    /// Same as [`Self::to_ascii`] with the pieces drawn in the given style
+    pub fn to_ascii_with(&self, style: BoardStyle) -> String {
+        let mut out = String::new();
+
+        for rank in (0..8).rev() {
+            out.push_str(&format!("{} |", rank + 1));
+
+            for file in 0..8 {
+                let piece = self.piece_at(Square::new(rank * 8 + file));
+
+                out.push(' ');
+                out.push(match style {
+                    BoardStyle::Unicode => piece.to_unicode(),
+                    BoardStyle::Letters => piece.to_char(),
+                });
+            }
+
+            out.push('\n');
+        }
+
+        out.push_str("    a b c d e f g h\n\n");
+
+        out.push_str(&format!(
+            "Side to move: {}\n",
+            if self.stm == Color::White {
+                "White"
+            } else {
+                "Black"
+            }
+        ));
+        out.push_str("Castling: ");
+        self.write_castling_fen(&mut out, FenStyle::Standard);
+        out.push_str("\nEn passant: ");
+        self.write_ep_fen(&mut out);
+        out.push('\n');
+
+        out
+    }
+
+    /// Returns the FEN representation of the position,
+    /// with the castling field written in the given style
+    pub fn fen_with(&self, style: FenStyle) -> Result<String> {
+        let mut fen = self.position_fen_with(style)?;
+
+        // halfmove clock
+        fen.push(' ');
+        fen.push_str(&self.halfm.to_string());
+
+        // fullmove number
+        fen.push(' ');
+        fen.push_str(&self.fullm.to_string());
+
+        Ok(fen)
+    }
+
+    fn write_board_fen(&self, fen: &mut String) -> Result<()> {
         for rank in (0..8).rev() {
             let mut empty_squares = 0;
 
@@ -393,7 +738,7 @@ impl Position {
                 let square = Square::new((rank * 8 + file) as u32);
                 let piece = self.piece_at(square);
 
-                if piece == Piece::none() {
+                if piece.is_none() {
                     empty_squares += 1;
                 } else {
                     if empty_squares > 0 {
@@ -429,51 +774,115 @@ impl Position {
             }
         }
 
+        Ok(())
+    }
+
+    fn position_fen_with(&self, style: FenStyle) -> Result<String> {
+        let mut fen = String::new();
+
+        // pieces
+        self.write_board_fen(&mut fen)?;
+
         // color
         fen.push(' ');
         fen.push(if self.stm == Color::White { 'w' } else { 'b' });
 
         // castling
         fen.push(' ');
+        self.write_castling_fen(&mut fen, style);
+
+        // ep square
+        fen.push(' ');
+        self.write_ep_fen(&mut fen);
+
+        Ok(fen)
+    }
+
+    fn write_castling_fen(&self, fen: &mut String, style: FenStyle) {
         let castling = self.castling_rights();
         if castling == CastlingRights::NONE {
             fen.push('-');
         } else {
-            if castling.contains(CastlingRights::WHITE_KING_SIDE) {
-                fen.push('K');
-            }
-            if castling.contains(CastlingRights::WHITE_QUEEN_SIDE) {
-                fen.push('Q');
-            }
-            if castling.contains(CastlingRights::BLACK_KING_SIDE) {
-                fen.push('k');
-            }
-            if castling.contains(CastlingRights::BLACK_QUEEN_SIDE) {
-                fen.push('q');
+            for (right, color, castle_type, c) in [
+                (
+                    CastlingRights::WHITE_KING_SIDE,
+                    Color::White,
+                    CastleType::Short,
+                    'K',
+                ),
+                (
+                    CastlingRights::WHITE_QUEEN_SIDE,
+                    Color::White,
+                    CastleType::Long,
+                    'Q',
+                ),
+                (
+                    CastlingRights::BLACK_KING_SIDE,
+                    Color::Black,
+                    CastleType::Short,
+                    'k',
+                ),
+                (
+                    CastlingRights::BLACK_QUEEN_SIDE,
+                    Color::Black,
+                    CastleType::Long,
+                    'q',
+                ),
+            ] {
+                if !castling.contains(right) {
+                    continue;
+                }
+
+                fen.push(match style {
+                    FenStyle::Standard => c,
+                    FenStyle::Shredder => {
+                        let file = (b'a' + self.castling_rook_file(color, castle_type)) as char;
+
+                        if color == Color::White {
+                            file.to_ascii_uppercase()
+                        } else {
+                            file
+                        }
+                    }
+                });
             }
         }
+    }
 
-        // ep square
-        fen.push(' ');
-        if self.enpassant == Square::NONE {
+    fn write_ep_fen(&self, fen: &mut String) {
+        if self.enpassant.is_none() {
             fen.push('-');
         } else {
-            // let file = (self.enpassant.to_u32() % 8) as u8;
-            // let rank = (self.enpassant.to_u32() / 8) as u8;
-            // fen.push((b'a' + file) as char);
-            // fen.push((b'1' + rank) as char);
             fen.push_str(&self.enpassant.to_string());
         }
+    }
 
-        // halfmove clock
-        fen.push(' ');
-        fen.push_str(&self.halfm.to_string());
+    /// Returns the file of the king on its back rank, the e-file if it is not there
+    fn king_file(&self, color: Color) -> u8 {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        let king = self.pieces_bb_color(color, PieceType::King) & Bitboard::from_rank(back_rank);
 
-        // fullmove number
-        fen.push(' ');
-        fen.push_str(&self.fullm.to_string());
+        if king.bits() == 0 {
+            4
+        } else {
+            (king.lsb().index() % 8) as u8
+        }
+    }
 
-        Ok(fen)
+    /// Returns the file of the rook the king castles with,
+    /// the outermost rook on the back rank on that side of the king.
+    /// Falls back to the a- or h-file if there is none.
+    fn castling_rook_file(&self, color: Color, castle_type: CastleType) -> u8 {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        let rook = Piece::new(PieceType::Rook, color);
+        let king_file = self.king_file(color);
+
+        let is_rook = |&file: &u8| self.piece_at(Square::new(back_rank * 8 + file as u32)) == rook;
+
+        match castle_type {
+            CastleType::Short => (king_file + 1..8).rev().find(is_rook).unwrap_or(7),
+            CastleType::Long => (0..king_file).find(is_rook).unwrap_or(0),
+        }
     }
 
     /// Create a position from a FEN string
@@ -483,6 +892,32 @@ impl Position {
         Ok(pos)
     }
 
+    /// Create a position from a FEN string, tolerating missing trailing fields
+    /// as found in EPD files. Missing fields default to `w - - 0 1`,
+    /// EPD operations after the fourth field (e.g. `bm e4;`) are ignored.
+    pub fn from_fen_lenient(fen: &str) -> Result<Self> {
+        let mut parts = fen.split_whitespace();
+
+        let placement = parts.next().ok_or(PositionError::InvalidFEN)?;
+        let stm = parts.next().unwrap_or("w");
+        let castling = parts.next().unwrap_or("-");
+        let ep = parts.next().unwrap_or("-");
+
+        let halfm = parts
+            .next()
+            .filter(|field| field.parse::<u8>().is_ok())
+            .unwrap_or("0");
+        let fullm = parts
+            .next()
+            .filter(|field| field.parse::<u16>().is_ok())
+            .unwrap_or("1");
+
+        Self::from_fen(&format!(
+            "{} {} {} {} {} {}",
+            placement, stm, castling, ep, halfm, fullm
+        ))
+    }
+
     /// Parse a FEN string and set the position
     fn parse_fen(&mut self, fen: &str) -> Result<()> {
         let mut parts = fen.split_whitespace();
@@ -513,7 +948,7 @@ impl Position {
                     _ => Piece::none(),
                 };
 
-                if piece == Piece::none() {
+                if piece.is_none() {
                     return Err(PositionError::InvalidFEN);
                 }
 
@@ -535,6 +970,20 @@ impl Position {
                 'Q' => self.castling_rights |= CastlingRights::WHITE_QUEEN_SIDE,
                 'k' => self.castling_rights |= CastlingRights::BLACK_KING_SIDE,
                 'q' => self.castling_rights |= CastlingRights::BLACK_QUEEN_SIDE,
+                'A'..='H' | 'a'..='h' => {
+                    let color = if c.is_ascii_uppercase() {
+                        Color::White
+                    } else {
+                        Color::Black
+                    };
+                    let file = c.to_ascii_lowercase() as u8 - b'a';
+
+                    self.castling_rights |= if file > self.king_file(color) {
+                        CastlingTraits::castling_rights(color, CastleType::Short)
+                    } else {
+                        CastlingTraits::castling_rights(color, CastleType::Long)
+                    };
+                }
                 _ => {}
             }
         }
@@ -552,10 +1001,34 @@ impl Position {
 
     /// Check if a square is attacked by the given color
     pub fn is_attacked(&self, sq: Square, c: Color) -> bool {
-        let pieces = |piece_type| self.pieces_bb_color(c, piece_type);
-        let occupied = self.occupied();
+        self.is_attacked_with(sq, c, self.occupied())
+    }
+
+    /// Check if a square is attacked by the given color with the given occupancy,
+    /// see [`Self::attackers_to`]
+    pub fn is_attacked_with(&self, sq: Square, c: Color, occupied: Bitboard) -> bool {
+        self.attackers_to(sq, c, occupied).bits() > 0
+    }
+
+    /// Returns the occupied squares on the rank, file or diagonal through `a` and `b`,
+    /// including `a` and `b` if they hold pieces. None if the squares aren't aligned.
+    /// Intersect with [`attacks::between`] to get only the pieces between them.
+    pub fn pieces_on_same_line(&self, a: Square, b: Square) -> Option<Bitboard> {
+        let line = attacks::line(a, b);
+
+        (a != b && line.bits() != 0).then(|| line & self.occupied())
+    }
 
-        // fast stuff first
+    /// Returns the pieces of the given color attacking a square
+    pub fn attackers(&self, sq: Square, c: Color) -> Bitboard {
+        self.attackers_to(sq, c, self.occupied())
+    }
+
+    /// Returns the pieces of the given color attacking a square with the given occupancy.
+    /// Sliders are blocked by the occupied squares and pieces missing from
+    /// the occupancy don't attack, e.g. when removing pieces in an exchange sequence.
+    pub fn attackers_to(&self, sq: Square, c: Color, occupied: Bitboard) -> Bitboard {
+        let pieces = |piece_type| self.pieces_bb_color(c, piece_type);
 
         (attacks::pawn(!c, sq) & pieces(PieceType::Pawn)
             | attacks::knight(sq) & pieces(PieceType::Knight)
@@ -563,72 +1036,2039 @@ impl Position {
             | attacks::bishop(sq, occupied)
                 & (pieces(PieceType::Bishop) | pieces(PieceType::Queen))
             | attacks::rook(sq, occupied) & (pieces(PieceType::Rook) | pieces(PieceType::Queen)))
-        .bits()
-            > 0
+            & occupied
     }
 
-    /// Returns the square of the king of the given color
-    pub fn king_sq(&self, c: Color) -> Square {
-        self.pieces_bb_color(c, PieceType::King).lsb()
-    }
+    /// Returns the pieces that would capture on `sq` in static exchange order,
+    /// starting with the side to move and always using the least valuable
+    /// attacker. X-ray attackers join once the pieces in front of them are gone.
+    /// The king only captures if the square is no longer defended.
+    pub fn capture_sequence_on(&self, sq: Square) -> Vec<(Color, PieceType)> {
+        let mut sequence = Vec::new();
+        let mut occupied = self.occupied();
+        let mut side = self.stm;
+
+        loop {
+            let attackers = self.attackers_to(sq, side, occupied);
+
+            let Some(pt) = [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+                PieceType::King,
+            ]
+            .into_iter()
+            .find(|&pt| (attackers & self.pieces_bb_type(pt)).bits() != 0) else {
+                break;
+            };
+
+            if pt == PieceType::King && self.attackers_to(sq, !side, occupied).bits() != 0 {
+                break;
+            }
 
-    /// Returns true if the given color is in check
-    pub fn is_checked(&self, c: Color) -> bool {
-        self.is_attacked(self.king_sq(c), !c)
+            let from = (attackers & self.pieces_bb_type(pt)).lsb();
+            occupied = occupied & !Bitboard::from_square(from);
+
+            sequence.push((side, pt));
+            side = !side;
+        }
+
+        sequence
     }
 
-    fn update_castling_rights_color(&mut self, color: Color, from: Square, to: Square) {
-        if color == Color::White {
-            if from == Square::E1 || to == Square::E1 {
-                self.castling_rights &= !CastlingRights::WHITE;
-            }
-            if from == Square::A1 || to == Square::A1 {
-                self.castling_rights &= !CastlingRights::WHITE_QUEEN_SIDE;
-            }
-            if from == Square::H1 || to == Square::H1 {
-                self.castling_rights &= !CastlingRights::WHITE_KING_SIDE;
-            }
-        } else {
-            if from == Square::E8 || to == Square::E8 {
-                self.castling_rights &= !CastlingRights::BLACK;
-            }
-            if from == Square::A8 || to == Square::A8 {
-                self.castling_rights &= !CastlingRights::BLACK_QUEEN_SIDE;
+    /// Static exchange evaluation, returns true if the exchange started by `mv`
+    /// on its target square wins at least `threshold` centipawns for the side
+    /// to move, using [`PieceValues::CLASSICAL`]. Castling, promotions and
+    /// en passant are scored as 0.
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        if mv.mtype() != MoveType::Normal {
+            return threshold <= 0;
+        }
+
+        let values = PieceValues::CLASSICAL;
+        let (from, to) = (mv.from(), mv.to());
+
+        let mut swap = values.value(self.piece_at(to).piece_type()) - threshold;
+        if swap < 0 {
+            return false;
+        }
+
+        swap = values.value(self.piece_at(from).piece_type()) - swap;
+        if swap <= 0 {
+            return true;
+        }
+
+        let mut occupied =
+            self.occupied() & !Bitboard::from_square(from) & !Bitboard::from_square(to);
+        let mut side = self.stm;
+        let mut res = true;
+
+        loop {
+            side = !side;
+
+            let attackers = self.attackers_to(to, side, occupied);
+            if attackers.bits() == 0 {
+                break;
             }
-            if from == Square::H8 || to == Square::H8 {
-                self.castling_rights &= !CastlingRights::BLACK_KING_SIDE;
+
+            res = !res;
+
+            let Some(pt) = [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ]
+            .into_iter()
+            .find(|&pt| (attackers & self.pieces_bb_type(pt)).bits() != 0) else {
+                // only the king is left, it can't capture into a defended square
+                return if self.attackers_to(to, !side, occupied).bits() != 0 {
+                    !res
+                } else {
+                    res
+                };
+            };
+
+            swap = values.value(pt) - swap;
+            if swap < res as i32 {
+                break;
             }
+
+            occupied =
+                occupied & !Bitboard::from_square((attackers & self.pieces_bb_type(pt)).lsb());
         }
+
+        res
     }
 
-    pub fn after_move(&self, mv: Move) -> Self {
-        let mut pos = *self;
-        pos.do_move(mv);
-        pos
+    /// Returns all squares attacked by the given color.
+    /// Squares occupied by pieces of the same color are included (defended squares).
+    pub fn attacks_by(&self, c: Color) -> Bitboard {
+        let occupied = self.occupied();
+        let mut attacked = Bitboard::new(0);
+
+        for sq in self.pieces_bb_color(c, PieceType::Pawn).iter() {
+            attacked |= attacks::pawn(c, sq);
+        }
+
+        for pt in [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ] {
+            for sq in self.pieces_bb_color(c, pt).iter() {
+                attacked |= attacks::piece_attacks(pt, sq, occupied);
+            }
+        }
+
+        attacked
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Returns the pawns of the given color with no enemy pawn in front of them
+    /// on the same or an adjacent file.
+    pub fn passed_pawns(&self, c: Color) -> Bitboard {
+        let theirs = self.pieces_bb_color(!c, PieceType::Pawn);
 
-    const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        // squares in front of the enemy pawns, seen from their side
+        let front = match c {
+            Color::White => Bitboard::new(theirs.south_fill().bits() >> 8),
+            Color::Black => Bitboard::new(theirs.north_fill().bits() << 8),
+        };
 
-    #[test]
-    fn test_startpos() {
-        let pos = Position::from_fen(STARTPOS).unwrap();
-        assert_eq!(pos.fen().unwrap(), STARTPOS);
+        let span = front.bits()
+            | (front.bits() << 1) & 0xFEFE_FEFE_FEFE_FEFE
+            | (front.bits() >> 1) & 0x7F7F_7F7F_7F7F_7F7F;
+
+        self.pieces_bb_color(c, PieceType::Pawn) & !Bitboard::new(span)
     }
 
-    #[test]
-    fn test_new() {
-        let pos = Position::new();
-        assert_eq!(pos.fen().unwrap(), STARTPOS);
+    /// Returns the number of squares the knights, bishops, rooks and queens
+    /// of the given color attack that are not occupied by their own pieces.
+    /// Pawns and the king are not included, pins and checks are ignored.
+    pub fn mobility(&self, c: Color) -> u32 {
+        let occupied = self.occupied();
+        let not_own = !self.pieces_bb(c);
+
+        [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ]
+        .into_iter()
+        .flat_map(|pt| {
+            self.pieces_bb_color(c, pt)
+                .iter()
+                .map(move |sq| (attacks::piece_attacks(pt, sq, occupied) & not_own).count())
+        })
+        .sum()
     }
 
-    #[test]
-    fn test_new_eq_fen() {
-        let pos = Position::new();
-        assert_eq!(pos, Position::from_fen(STARTPOS).unwrap());
+    /// Returns for every square the number of White pieces attacking it
+    /// minus the number of Black pieces attacking it, x-rays are not counted.
+    pub fn control(&self) -> [i8; 64] {
+        let occupied = self.occupied();
+        let mut control = [0i8; 64];
+
+        for sq in self.occupied().iter() {
+            let (pt, color) = self.piece_at(sq).parts();
+
+            let attacked = match pt {
+                PieceType::Pawn => attacks::pawn(color, sq),
+                _ => attacks::piece_attacks(pt, sq, occupied),
+            };
+
+            let delta = if color == Color::White { 1 } else { -1 };
+
+            for target in attacked.iter() {
+                control[target.index() as usize] += delta;
+            }
+        }
+
+        control
+    }
+
+    /// Returns true if the given color has any piece besides king and pawns
+    pub fn has_non_pawn_material(&self, c: Color) -> bool {
+        let pawns_and_king = self.bb[PieceType::Pawn.ordinal() as usize]
+            | self.bb[PieceType::King.ordinal() as usize];
+
+        self.bb_color[c as usize] & !pawns_and_king != 0
+    }
+
+    /// Returns the summed value of the knights, bishops, rooks and queens of the given color
+    pub fn non_pawn_material(&self, c: Color, values: &PieceValues) -> i32 {
+        [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+        ]
+        .into_iter()
+        .map(|pt| self.pieces_bb_color(c, pt).count() as i32 * values.value(pt))
+        .sum()
+    }
+
+    /// Returns the material of White minus the material of Black
+    pub fn material_balance(&self, values: &PieceValues) -> i32 {
+        let material = |c| {
+            self.non_pawn_material(c, values)
+                + self.pieces_bb_color(c, PieceType::Pawn).count() as i32 * values.pawn
+        };
+
+        material(Color::White) - material(Color::Black)
+    }
+
+    /// Returns the game phase from 24 with all minor and major pieces
+    /// on the board down to 0 with only kings and pawns left.
+    /// Knights and bishops count 1, rooks 2 and queens 4, extra pieces from
+    /// promotions are capped at 24.
+    pub fn phase(&self) -> i32 {
+        let phase = self.pieces_bb_type(PieceType::Knight).count()
+            + self.pieces_bb_type(PieceType::Bishop).count()
+            + self.pieces_bb_type(PieceType::Rook).count() * 2
+            + self.pieces_bb_type(PieceType::Queen).count() * 4;
+
+        phase.min(24) as i32
+    }
+
+    /// Returns the material signature, e.g. `KQKR`, listing the pieces of each
+    /// side from king to pawns with the side with more material
+    /// ([`PieceValues::CLASSICAL`]) first, White first if both are equal.
+    pub fn material_signature(&self) -> String {
+        let side = |c| {
+            [
+                PieceType::King,
+                PieceType::Queen,
+                PieceType::Rook,
+                PieceType::Bishop,
+                PieceType::Knight,
+                PieceType::Pawn,
+            ]
+            .into_iter()
+            .flat_map(|pt| {
+                let count = self.pieces_bb_color(c, pt).count() as usize;
+                std::iter::repeat_n(Piece::new(pt, Color::White).to_char(), count)
+            })
+            .collect::<String>()
+        };
+
+        let (white, black) = (side(Color::White), side(Color::Black));
+
+        if self.material_balance(&PieceValues::CLASSICAL) >= 0 {
+            white + &black
+        } else {
+            black + &white
+        }
+    }
+
+    /// Returns a key encoding the number of pawns, knights, bishops, rooks
+    /// and queens of each color in 4 bits each, White in the low 20 bits.
+    /// Unlike [`Self::material_signature`] the key distinguishes the colors.
+    pub fn material_key(&self) -> u64 {
+        let mut key = 0;
+
+        for (i, c) in [Color::White, Color::Black].into_iter().enumerate() {
+            for pt in [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ] {
+                let count = self.pieces_bb_color(c, pt).count() as u64;
+                key |= count.min(15) << ((i * 5 + pt.ordinal() as usize) * 4);
+            }
+        }
+
+        key
+    }
+
+    /// Interpolate between a middlegame and an endgame score by the `phase`
+    pub fn taper(&self, mg: i32, eg: i32) -> i32 {
+        let phase = self.phase();
+
+        (mg * phase + eg * (24 - phase)) / 24
+    }
+
+    /// Returns the square of the king of the given color
+    pub fn king_sq(&self, c: Color) -> Square {
+        self.pieces_bb_color(c, PieceType::King).lsb()
+    }
+
+    /// Returns true if the kings stand on the same file or rank with a single
+    /// square between them, regardless of the side to move
+    pub fn kings_in_opposition(&self) -> bool {
+        let white = self.king_sq(Color::White);
+        let black = self.king_sq(Color::Black);
+
+        (white.file() == black.file() || white.rank() == black.rank())
+            && Square::distance(white, black) == 2
+    }
+
+    /// Returns the king square of the given color together with its adjacent squares
+    pub fn king_ring(&self, c: Color) -> Bitboard {
+        let ksq = self.king_sq(c);
+        attacks::king(ksq) | Bitboard::from_square(ksq)
+    }
+
+    /// Returns the number of pieces of the attacker that attack the ring
+    /// around the enemy king, see [`Self::king_ring`]
+    pub fn king_attackers_count(&self, attacker: Color) -> u32 {
+        let ring = self.king_ring(!attacker);
+        let occupied = self.occupied();
+
+        let pawns = self
+            .pieces_bb_color(attacker, PieceType::Pawn)
+            .iter()
+            .filter(|&sq| (attacks::pawn(attacker, sq) & ring).bits() != 0)
+            .count();
+
+        let pieces = [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ]
+        .into_iter()
+        .flat_map(|pt| {
+            self.pieces_bb_color(attacker, pt)
+                .iter()
+                .filter(move |&sq| (attacks::piece_attacks(pt, sq, occupied) & ring).bits() != 0)
+        })
+        .count();
+
+        (pawns + pieces) as u32
+    }
+
+    /// Returns the weighted number of enemy pieces attacking the king ring of
+    /// `defending`, counting 2 for a knight or bishop, 3 for a rook and 5 for a queen.
+    /// Pawns and the king don't contribute.
+    pub fn king_danger(&self, defending: Color) -> i32 {
+        let attacker = !defending;
+        let ring = self.king_ring(defending);
+        let occupied = self.occupied();
+
+        [
+            (PieceType::Knight, 2),
+            (PieceType::Bishop, 2),
+            (PieceType::Rook, 3),
+            (PieceType::Queen, 5),
+        ]
+        .into_iter()
+        .map(|(pt, weight)| {
+            let attackers = self
+                .pieces_bb_color(attacker, pt)
+                .iter()
+                .filter(|&sq| (attacks::piece_attacks(pt, sq, occupied) & ring).bits() != 0)
+                .count();
+
+            attackers as i32 * weight
+        })
+        .sum()
+    }
+
+    /// Returns true if the given color is in check
+    pub fn is_checked(&self, c: Color) -> bool {
+        self.is_attacked(self.king_sq(c), !c)
+    }
+
+    /// Returns true if the side to move may stand pat in a quiescence search,
+    /// i.e. it isn't in check
+    pub fn can_stand_pat(&self) -> bool {
+        !self.is_checked(self.stm)
+    }
+
+    fn update_castling_rights_color(&mut self, color: Color, from: Square, to: Square) {
+        if color == Color::White {
+            if from == Square::E1 || to == Square::E1 {
+                self.castling_rights &= !CastlingRights::WHITE;
+            }
+            if from == Square::A1 || to == Square::A1 {
+                self.castling_rights &= !CastlingRights::WHITE_QUEEN_SIDE;
+            }
+            if from == Square::H1 || to == Square::H1 {
+                self.castling_rights &= !CastlingRights::WHITE_KING_SIDE;
+            }
+        } else {
+            if from == Square::E8 || to == Square::E8 {
+                self.castling_rights &= !CastlingRights::BLACK;
+            }
+            if from == Square::A8 || to == Square::A8 {
+                self.castling_rights &= !CastlingRights::BLACK_QUEEN_SIDE;
+            }
+            if from == Square::H8 || to == Square::H8 {
+                self.castling_rights &= !CastlingRights::BLACK_KING_SIDE;
+            }
+        }
+    }
+
+    pub fn after_move(&self, mv: Move) -> Self {
+        let mut pos = *self;
+        pos.do_move(mv);
+        pos
+    }
+
+    /// Returns the position after passing the move to the opponent,
+    /// clearing the en passant square and advancing the move counters
+    pub fn after_null_move(&self) -> Self {
+        let mut pos = *self;
+
+        pos.halfm = pos.halfm.saturating_add(1);
+        if pos.stm == Color::Black {
+            pos.fullm += 1;
+        }
+
+        pos.enpassant = Square::NONE;
+        pos.stm = !pos.stm;
+        pos
+    }
+
+    /// Plays a legal move in place and returns what is needed to take it back
+    /// with [`Position::unmake`], avoiding a copy of the whole position.
+    pub fn make(&mut self, mv: Move) -> UndoInfo {
+        let undo = UndoInfo {
+            captured: self.captured_piece(mv).unwrap_or(Piece::none()),
+            castling_rights: self.castling_rights,
+            enpassant: self.enpassant,
+            halfm: self.halfm,
+            fullm: self.fullm,
+        };
+
+        self.do_move(mv);
+        undo
+    }
+
+    /// Takes back a move played with [`Position::make`]
+    pub fn unmake(&mut self, mv: Move, undo: UndoInfo) {
+        self.stm = !self.stm;
+
+        let us = self.stm;
+        let from = mv.from();
+        let to = mv.to();
+
+        match mv.mtype() {
+            MoveType::Castle => {
+                let (king_to, rook_to) = match (mv.castle_type(), us) {
+                    (CastleType::Short, Color::White) => (Square::G1, Square::F1),
+                    (CastleType::Short, Color::Black) => (Square::G8, Square::F8),
+                    (CastleType::Long, Color::White) => (Square::C1, Square::D1),
+                    (CastleType::Long, Color::Black) => (Square::C8, Square::D8),
+                };
+
+                let king = self.piece_at(king_to);
+                let rook = self.piece_at(rook_to);
+
+                self.remove_piecetype(us, PieceType::King, king_to);
+                self.remove_piecetype(us, PieceType::Rook, rook_to);
+                self.place_piece(us, rook, to);
+                self.place_piece(us, king, from);
+            }
+            MoveType::Promotion => {
+                let promoted = self.piece_at(to);
+
+                self.remove_piecetype(us, promoted.piece_type(), to);
+                self.place_piece(us, Piece::new(PieceType::Pawn, us), from);
+            }
+            MoveType::EnPassant => {
+                let pawn = self.piece_at(to);
+
+                self.remove_piecetype(us, PieceType::Pawn, to);
+                self.place_piece(us, pawn, from);
+                self.place_piece(!us, undo.captured, Square::new(to.index() ^ 8));
+            }
+            MoveType::Normal => {
+                let piece = self.piece_at(to);
+
+                self.remove_piecetype(us, piece.piece_type(), to);
+                self.place_piece(us, piece, from);
+            }
+        }
+
+        if !mv.is_en_passant() && undo.captured.is_some() {
+            self.place_piece(!us, undo.captured, to);
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.enpassant = undo.enpassant;
+        self.halfm = undo.halfm;
+        self.fullm = undo.fullm;
+    }
+
+    /// List the differences to `other` in readable form, one line per difference,
+    /// e.g. `e4: P vs .`. Meant for debugging, empty if the positions are equal.
+    pub fn diff(&self, other: &Position) -> Vec<String> {
+        let mut diffs = Vec::new();
+
+        for index in 0..64 {
+            let sq = Square::new(index);
+            let (a, b) = (self.piece_at(sq), other.piece_at(sq));
+
+            if a != b {
+                diffs.push(format!("{}: {} vs {}", sq, a.to_char(), b.to_char()));
+            }
+        }
+
+        if self.stm != other.stm {
+            diffs.push(format!("side to move: {:?} vs {:?}", self.stm, other.stm));
+        }
+
+        let castling = |pos: &Position| {
+            let mut fen = String::new();
+            pos.write_castling_fen(&mut fen, FenStyle::Standard);
+            fen
+        };
+
+        let ep = |pos: &Position| {
+            let mut fen = String::new();
+            pos.write_ep_fen(&mut fen);
+            fen
+        };
+
+        if self.castling_rights != other.castling_rights {
+            diffs.push(format!(
+                "castling rights: {} vs {}",
+                castling(self),
+                castling(other)
+            ));
+        }
+
+        if self.enpassant != other.enpassant {
+            diffs.push(format!("en passant: {} vs {}", ep(self), ep(other)));
+        }
+
+        if self.halfm != other.halfm {
+            diffs.push(format!("rule50: {} vs {}", self.halfm, other.halfm));
+        }
+
+        if self.fullm != other.fullm {
+            diffs.push(format!("fullmove: {} vs {}", self.fullm, other.fullm));
+        }
+
+        diffs
+    }
+
+    /// Returns a key identifying the position independent of the move counters.
+    /// It covers piece placement, side to move, castling rights and the en passant
+    /// square, the latter only if a pawn of the side to move could capture on it.
+    pub fn canonical_key(&self) -> [u8; 24] {
+        let mut pos = *self;
+
+        if !pos.is_ep_capturable() {
+            pos.enpassant = Square::NONE;
+        }
+
+        let mut key = [0u8; 24];
+        CompressedPosition::compress(&pos).write_to_big_endian(&mut key);
+        key
+    }
+
+    /// Compare two positions like `==`, but ignoring the en passant square,
+    /// even if a pawn could capture on it. Unlike [`Self::canonical_key`]
+    /// the move counters are compared.
+    pub fn eq_ignore_ep(&self, other: &Position) -> bool {
+        let mut lhs = *self;
+        let mut rhs = *other;
+        lhs.enpassant = Square::NONE;
+        rhs.enpassant = Square::NONE;
+
+        lhs == rhs
+    }
+
+    /// Returns for each piece type, indexed by its ordinal, the squares from which
+    /// a piece of that type of the side to move would attack the enemy king.
+    /// A move gives a direct check if the moved piece lands on a square of its
+    /// mask, except for promotions and castling where the board around the target
+    /// square changes. The king can never give check.
+    pub fn check_squares(&self) -> [Bitboard; 6] {
+        let ksq = self.king_sq(!self.stm);
+        let occupied = self.occupied();
+
+        let bishop = attacks::bishop(ksq, occupied);
+        let rook = attacks::rook(ksq, occupied);
+
+        [
+            attacks::pawn(!self.stm, ksq),
+            attacks::knight(ksq),
+            bishop,
+            rook,
+            bishop | rook,
+            Bitboard::new(0),
+        ]
+    }
+
+    /// Returns true if the move checks the enemy king, directly or discovered
+    pub fn gives_check(&self, mv: Move) -> bool {
+        if !mv.is_normal() {
+            return self.after_move(mv).is_checked(!self.stm);
+        }
+
+        let pt = self.piece_at(mv.from()).piece_type();
+
+        self.check_squares()[pt.ordinal() as usize].sq_set(mv.to())
+            || self.gives_discovered_check(mv)
+    }
+
+    /// Returns true if moving the piece off `mv.from()` uncovers an attack
+    /// of one of our sliders on the enemy king.
+    /// A move that is both a direct and a discovered check is a double check.
+    pub fn gives_discovered_check(&self, mv: Move) -> bool {
+        let ksq = self.king_sq(!self.stm);
+
+        match mv.mtype() {
+            // the rook can only give a direct check, nothing is behind the king
+            MoveType::Castle => false,
+            // the captured pawn also leaves the board, look at the resulting position
+            MoveType::EnPassant => {
+                let after = self.after_move(mv);
+                let occupied = after.occupied();
+                let sliders = attacks::bishop(ksq, occupied)
+                    & (after.pieces_bb_color(self.stm, PieceType::Bishop)
+                        | after.pieces_bb_color(self.stm, PieceType::Queen))
+                    | attacks::rook(ksq, occupied)
+                        & (after.pieces_bb_color(self.stm, PieceType::Rook)
+                            | after.pieces_bb_color(self.stm, PieceType::Queen));
+
+                sliders.iter().any(|sq| sq != mv.to())
+            }
+            MoveType::Normal | MoveType::Promotion => {
+                let empty = Bitboard::new(0);
+                let snipers = attacks::bishop(ksq, empty)
+                    & (self.pieces_bb_color(self.stm, PieceType::Bishop)
+                        | self.pieces_bb_color(self.stm, PieceType::Queen))
+                    | attacks::rook(ksq, empty)
+                        & (self.pieces_bb_color(self.stm, PieceType::Rook)
+                            | self.pieces_bb_color(self.stm, PieceType::Queen));
+                let from = Bitboard::from_square(mv.from());
+
+                snipers.iter().any(|sq| {
+                    attacks::between(sq, ksq) & self.occupied() == from
+                        && !attacks::line(sq, ksq).sq_set(mv.to())
+                })
+            }
+        }
+    }
+
+    /// Returns a lazy generator of the legal moves, captures first
+    pub fn move_generator(&self) -> MoveGen<'_> {
+        MoveGen::new(self)
+    }
+
+    /// Returns the enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> Bitboard {
+        let us = self.stm;
+        let ksq = self.king_sq(us);
+        let occupied = self.occupied();
+        let pieces = |pt| self.pieces_bb_color(!us, pt);
+
+        attacks::pawn(us, ksq) & pieces(PieceType::Pawn)
+            | attacks::knight(ksq) & pieces(PieceType::Knight)
+            | attacks::bishop(ksq, occupied)
+                & (pieces(PieceType::Bishop) | pieces(PieceType::Queen))
+            | attacks::rook(ksq, occupied) & (pieces(PieceType::Rook) | pieces(PieceType::Queen))
+    }
+
+    /// Returns the pieces of the side to move that are pinned to their king
+    pub fn pinned(&self) -> Bitboard {
+        let us = self.stm;
+        let ksq = self.king_sq(us);
+        let occupied = self.occupied();
+        let ours = self.pieces_bb(us);
+        let mut pinned = Bitboard::new(0);
+
+        for sniper in self.snipers(ksq, !us).iter() {
+            let blockers = attacks::between(ksq, sniper) & occupied;
+
+            if blockers.count() == 1 && (blockers & ours).bits() != 0 {
+                pinned |= blockers;
+            }
+        }
+
+        pinned
+    }
+
+    /// Computes the checkers and pinned pieces of the side to move in a single pass
+    /// over the sliders aligned with the king
+    pub fn check_info(&self) -> CheckInfo {
+        let us = self.stm;
+        let ksq = self.king_sq(us);
+        let occupied = self.occupied();
+        let ours = self.pieces_bb(us);
+
+        let mut info = CheckInfo {
+            checkers: attacks::pawn(us, ksq) & self.pieces_bb_color(!us, PieceType::Pawn)
+                | attacks::knight(ksq) & self.pieces_bb_color(!us, PieceType::Knight),
+            pinned: Bitboard::new(0),
+            pin_rays: Bitboard::new(0),
+        };
+
+        for sniper in self.snipers(ksq, !us).iter() {
+            let ray = attacks::between(ksq, sniper);
+            let blockers = ray & occupied;
+
+            match blockers.count() {
+                0 => info.checkers |= Bitboard::from_square(sniper),
+                1 if (blockers & ours).bits() != 0 => {
+                    info.pinned |= blockers;
+                    info.pin_rays |= ray | Bitboard::from_square(sniper);
+                }
+                _ => {}
+            }
+        }
+
+        info
+    }
+
+    /// Returns the sliders of the given color that would attack the square on an empty board
+    fn snipers(&self, sq: Square, c: Color) -> Bitboard {
+        let empty = Bitboard::new(0);
+        let pieces = |pt| self.pieces_bb_color(c, pt);
+
+        attacks::bishop(sq, empty) & (pieces(PieceType::Bishop) | pieces(PieceType::Queen))
+            | attacks::rook(sq, empty) & (pieces(PieceType::Rook) | pieces(PieceType::Queen))
+    }
+
+    /// Returns true if a pawn of the side to move attacks the en passant square
+    fn is_ep_capturable(&self) -> bool {
+        self.enpassant.is_some()
+            && (attacks::pawn(!self.stm, self.enpassant)
+                & self.pieces_bb_color(self.stm, PieceType::Pawn))
+            .bits()
+                != 0
+    }
+
+    /// Returns the Zobrist hash of the piece placement, side to move, castling rights
+    /// and the en passant square (only if it can be captured), the move counters are ignored.
+    pub fn zobrist_hash(&self) -> u64 {
+        self.hash_with(&KEYS)
+    }
+
+    /// Returns the key engines compare for repetition detection, a Zobrist hash
+    /// of everything but the move counters. Unlike [`Self::zobrist_hash`] and
+    /// [`Self::canonical_key`] the en passant file only counts if the capture
+    /// is legal, not just if a pawn attacks the square.
+    pub fn repetition_key(&self) -> u64 {
+        let mut hash = self.zobrist_hash();
+
+        if self.is_ep_capturable() && !self.legal_moves().iter().any(|mv| mv.is_en_passant()) {
+            hash ^= KEYS.ep_file[(self.enpassant.index() % 8) as usize];
+        }
+
+        hash
+    }
+
+    /// Updates `current_hash`, the [`Self::zobrist_hash`] of this position, for a null
+    /// move by toggling the side to move and dropping the en passant file. Equal to
+    /// `self.after_null_move().zobrist_hash()` without recomputing it from scratch.
+    pub fn hash_after_null(&self, current_hash: u64) -> u64 {
+        let mut hash = current_hash ^ KEYS.side;
+
+        if self.is_ep_capturable() {
+            hash ^= KEYS.ep_file[(self.enpassant.index() % 8) as usize];
+        }
+
+        hash
+    }
+
+    /// Returns a 128 bit key made of two independent Zobrist hashes,
+    /// for dedup sets large enough that 64 bit collisions become likely.
+    /// Covers the same state as [`Self::zobrist_hash`], which is the lower half.
+    pub fn key128(&self) -> u128 {
+        ((self.hash_with(&KEYS_HIGH) as u128) << 64) | self.hash_with(&KEYS) as u128
+    }
+
+    fn hash_with(&self, keys: &ZobristKeys) -> u64 {
+        let mut hash = 0;
+
+        for sq in self.occupied().iter() {
+            hash ^= keys.pieces[self.piece_at(sq).id() as usize][sq.index() as usize];
+        }
+
+        if self.stm == Color::Black {
+            hash ^= keys.side;
+        }
+
+        for (i, right) in [
+            CastlingRights::WHITE_KING_SIDE,
+            CastlingRights::WHITE_QUEEN_SIDE,
+            CastlingRights::BLACK_KING_SIDE,
+            CastlingRights::BLACK_QUEEN_SIDE,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if self.castling_rights.contains(right) {
+                hash ^= keys.castling[i];
+            }
+        }
+
+        if self.is_ep_capturable() {
+            hash ^= keys.ep_file[(self.enpassant.index() % 8) as usize];
+        }
+
+        hash
+    }
+
+    /// Returns all legal moves for the side to move
+    pub fn legal_moves(&self) -> ArrayVec<Move, 256> {
+        let info = self.check_info();
+        let ksq = self.king_sq(self.stm);
+
+        let mut moves = attacks::pseudo_legal_moves(self);
+        moves.retain(|mv| self.is_legal_with(*mv, ksq, &info));
+        moves
+    }
+
+    /// Returns the number of legal moves, counted while the pseudo-legal moves
+    /// are generated without collecting them into a list
+    pub fn legal_move_count(&self) -> u32 {
+        let info = self.check_info();
+        let ksq = self.king_sq(self.stm);
+        let mut count = 0;
+
+        attacks::for_each_pseudo_legal_move(self, |mv| {
+            if self.is_legal_with(mv, ksq, &info) {
+                count += 1;
+            }
+        });
+
+        count
+    }
+
+    /// Returns the legal moves of the piece on `from`, empty if the square
+    /// is empty or holds a piece of the side not to move.
+    /// Castling moves are included for the king, encoded as king captures rook.
+    pub fn legal_moves_from(&self, from: Square) -> ArrayVec<Move, 32> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from() == from)
+            .collect()
+    }
+
+    /// Returns true if the move can be generated in this position: the moving
+    /// piece belongs to the side to move, it can reach the target square and the
+    /// target isn't occupied by an own piece. Checks are not considered.
+    pub fn is_pseudo_legal(&self, mv: Move) -> bool {
+        attacks::is_pseudo_legal(self, mv)
+    }
+
+    /// Returns true if the move is pseudo legal and doesn't leave the own king in check
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.is_pseudo_legal(mv)
+            && self.is_legal_with(mv, self.king_sq(self.stm), &self.check_info())
+    }
+
+    /// Play the move if it is legal, otherwise the position is left unchanged
+    pub fn do_move_checked(&mut self, mv: Move) -> Result<()> {
+        if !self.is_legal(mv) {
+            return Err(PositionError::IllegalMove);
+        }
+
+        self.do_move(mv);
+        Ok(())
+    }
+
+    /// Returns true if the side to move is not in check and has no legal
+    /// capture or promotion
+    pub fn is_quiet(&self) -> bool {
+        if self.checkers().bits() != 0 {
+            return false;
+        }
+
+        !self
+            .legal_moves()
+            .iter()
+            .any(|mv| mv.is_promotion() || self.captured_piece(*mv).is_some())
+    }
+
+    /// Returns true if the side to move has a capture or promotion, using only
+    /// bitboard intersections instead of generating moves. The check is
+    /// pseudo-legal, pins and checks are ignored, so it may report a tactical
+    /// move that can't legally be played but never misses one.
+    pub fn has_tactical_moves(&self) -> bool {
+        let us = self.stm;
+        let them = self.them();
+        let occupied = self.occupied();
+        let pawns = self.pieces_bb_color(us, PieceType::Pawn);
+
+        // promotion pushes
+        let promoting = if us == Color::White {
+            (pawns.bits() & Bitboard::from_rank(6).bits()) << 8
+        } else {
+            (pawns.bits() & Bitboard::from_rank(1).bits()) >> 8
+        };
+
+        if promoting & !occupied.bits() != 0 {
+            return true;
+        }
+
+        let mut targets = them;
+        if self.enpassant.is_some() {
+            targets |= Bitboard::from_square(self.enpassant);
+        }
+
+        if pawns
+            .iter()
+            .any(|sq| (attacks::pawn(us, sq) & targets).bits() != 0)
+        {
+            return true;
+        }
+
+        [
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ]
+        .into_iter()
+        .any(|pt| {
+            self.pieces_bb_color(us, pt)
+                .iter()
+                .any(|sq| (attacks::piece_attacks(pt, sq, occupied) & them).bits() != 0)
+        })
+    }
+
+    /// Legality of a pseudo legal move, only king moves and en passant
+    /// captures need to be played out on the board
+    fn is_legal_with(&self, mv: Move, ksq: Square, info: &CheckInfo) -> bool {
+        if mv.from() == ksq || mv.is_en_passant() {
+            return !self.after_move(mv).is_checked(self.stm);
+        }
+
+        // a check can only be blocked or the checker captured
+        if !attacks::in_check_mask(ksq, info.checkers).sq_set(mv.to()) {
+            return false;
+        }
+
+        !info.pinned.sq_set(mv.from()) || attacks::line(ksq, mv.from()).sq_set(mv.to())
+    }
+}
+
+/// Infer the game result from White's point of view from the final position,
+/// 1 if Black is checkmated, -1 if White is checkmated and 0 otherwise, which
+/// includes stalemate, insufficient material and the fifty-move rule but also
+/// positions where the game isn't over.
+pub fn game_result(final_pos: &Position) -> i16 {
+    if !final_pos.is_checkmate() {
+        return 0;
+    }
+
+    match final_pos.side_to_move() {
+        Color::White => -1,
+        Color::Black => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_startpos() {
+        let pos = Position::from_fen(STARTPOS).unwrap();
+        assert_eq!(pos.fen().unwrap(), STARTPOS);
+    }
+
+    #[test]
+    fn test_new() {
+        let pos = Position::new();
+        assert_eq!(pos.fen().unwrap(), STARTPOS);
+    }
+
+    #[test]
+    fn test_new_eq_fen() {
+        let pos = Position::new();
+        assert_eq!(pos, Position::from_fen(STARTPOS).unwrap());
+    }
+
+    #[test]
+    fn test_from_board_array() {
+        let back_rank = [
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Rook,
+        ];
+
+        let mut squares = [None; 64];
+        for file in 0..8 {
+            squares[file] = Some(Piece::new(back_rank[file], Color::White));
+            squares[8 + file] = Some(Piece::WHITE_PAWN);
+            squares[48 + file] = Some(Piece::BLACK_PAWN);
+            squares[56 + file] = Some(Piece::new(back_rank[file], Color::Black));
+        }
+
+        let mut pos = Position::from_board_array(&squares, Color::White).unwrap();
+        pos.set_castling_rights(CastlingRights::ALL);
+        assert_eq!(pos.fen().unwrap(), STARTPOS);
+
+        squares[4] = None;
+        assert_eq!(
+            Position::from_board_array(&squares, Color::White),
+            Err(PositionError::InvalidKingCount)
+        );
+
+        squares[4] = Some(Piece::WHITE_KING);
+        squares[3] = Some(Piece::WHITE_KING);
+        assert_eq!(
+            Position::from_board_array(&squares, Color::Black),
+            Err(PositionError::InvalidKingCount)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let validate = |fen| Position::from_fen(fen).unwrap().validate();
+
+        assert_eq!(Position::new().validate(), Ok(()));
+        assert_eq!(
+            validate("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 17"),
+            Ok(())
+        );
+
+        assert_eq!(
+            Position::empty().validate(),
+            Err(PositionError::InvalidKingCount)
+        );
+        assert_eq!(
+            validate("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Err(PositionError::PawnOnBackRank)
+        );
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"),
+            Err(PositionError::OpponentInCheck)
+        );
+        assert_eq!(
+            validate("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 3"),
+            Err(PositionError::InvalidEnPassant)
+        );
+        // en passant squares on the edge ranks
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/4K3 b - a1 0 1"),
+            Err(PositionError::InvalidEnPassant)
+        );
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/4K3 w - h8 0 1"),
+            Err(PositionError::InvalidEnPassant)
+        );
+        assert_eq!(
+            validate("4k3/8/8/8/8/8/8/4K3 w K - 0 1"),
+            Err(PositionError::InvalidCastlingRights)
+        );
+    }
+
+    #[test]
+    fn test_board_and_position_fen() {
+        let pos = Position::new();
+        assert_eq!(
+            pos.board_fen().unwrap(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"
+        );
+        assert_eq!(
+            pos.position_fen().unwrap(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"
+        );
+
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        let pos = Position::from_fen(fen).unwrap();
+        assert_eq!(
+            pos.position_fen().unwrap(),
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6"
+        );
+    }
+
+    #[test]
+    fn test_fen_round_trip() {
+        for fen in [
+            "8/8/8/8/8/8/8/4k2K w - - 0 1",
+            "8/8/3k4/8/8/8/8/7K b - - 12 60",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2",
+        ] {
+            assert_eq!(Position::from_fen(fen).unwrap().fen().unwrap(), fen);
+        }
+
+        let pos = Position::from_fen("8/8/8/8/8/8/8/4k2K w - - 0 1").unwrap();
+        assert_eq!(pos.board_fen().unwrap(), "8/8/8/8/8/8/8/4k2K");
+        assert_eq!(pos.ep_square(), Square::NONE);
+    }
+
+    #[test]
+    fn test_fen_shredder() {
+        let pos = Position::new();
+        assert_eq!(
+            pos.fen_with(FenStyle::Shredder).unwrap(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1"
+        );
+
+        let fen = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1";
+        let pos = Position::from_fen(fen).unwrap();
+        assert_eq!(pos.castling_rights(), CastlingRights::ALL);
+        assert_eq!(pos.fen_with(FenStyle::Shredder).unwrap(), fen);
+        assert_eq!(
+            pos.fen().unwrap(),
+            "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1"
+        );
+
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/1R2K2R w HB - 0 1").unwrap();
+        assert_eq!(pos.castling_rights(), CastlingRights::WHITE);
+        assert_eq!(pos.fen().unwrap(), "4k3/8/8/8/8/8/8/1R2K2R w KQ - 0 1");
+        assert_eq!(
+            pos.fen_with(FenStyle::Shredder).unwrap(),
+            "4k3/8/8/8/8/8/8/1R2K2R w HB - 0 1"
+        );
+    }
+
+    #[test]
+    fn test_attacks_by() {
+        let pos = Position::new();
+        let white = pos.attacks_by(Color::White);
+
+        assert_eq!(white & Bitboard::from_rank(2), Bitboard::from_rank(2));
+        assert!((white & Bitboard::from_rank(1)).count() > 0);
+        assert!((white & Bitboard::from_rank(0)).count() > 0);
+        assert_eq!((white & Bitboard::from_rank(3)).count(), 0);
+
+        // the board is symmetric
+        assert_eq!(
+            pos.attacks_by(Color::Black).bits(),
+            white.bits().swap_bytes()
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let pos = Position::new();
+        assert!(pos.diff(&pos).is_empty());
+
+        let other =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1")
+                .unwrap();
+        assert_eq!(pos.diff(&other), vec!["e2: P vs .", "e4: . vs P"]);
+
+        let other =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b Kq - 3 1").unwrap();
+        assert_eq!(
+            pos.diff(&other),
+            vec![
+                "side to move: White vs Black",
+                "castling rights: KQkq vs Kq",
+                "rule50: 0 vs 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let pos = Position::from_fen(STARTPOS).unwrap();
+        let other =
+            Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 12 40")
+                .unwrap();
+        assert_eq!(pos.canonical_key(), other.canonical_key());
+
+        // the ep square can't be captured and is ignored
+        let pos = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        let other = Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(pos.canonical_key(), other.canonical_key());
+
+        let pos = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let other = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(pos.canonical_key(), other.canonical_key());
+    }
+
+    #[test]
+    fn test_has_non_pawn_material() {
+        let pos = Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert!(!pos.has_non_pawn_material(Color::White));
+        assert!(!pos.has_non_pawn_material(Color::Black));
+
+        let pos = Position::new();
+        assert!(pos.has_non_pawn_material(Color::White));
+        assert!(pos.has_non_pawn_material(Color::Black));
+    }
+
+    #[test]
+    fn test_gives_discovered_check() {
+        let pos = Position::from_fen("4k3/8/8/8/4B3/8/8/K3R3 w - - 0 1").unwrap();
+
+        // the bishop uncovers the rook
+        let mv = Move::normal(Square::new(28), Square::new(35));
+        assert!(pos.gives_discovered_check(mv));
+        assert!(pos.gives_check(mv));
+
+        // double check, the bishop also attacks the king from g6
+        let mv = Move::normal(Square::new(28), Square::new(46));
+        assert!(pos.gives_discovered_check(mv));
+        assert!(pos.gives_check(mv));
+
+        // a king move uncovers nothing
+        let mv = Move::normal(Square::A1, Square::new(1));
+        assert!(!pos.gives_discovered_check(mv));
+        assert!(!pos.gives_check(mv));
+
+        // the rook gives a direct check only
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/K6R w - - 0 1").unwrap();
+        let mv = Move::normal(Square::H1, Square::H8);
+        assert!(pos.gives_check(mv));
+        assert!(!pos.gives_discovered_check(mv));
+
+        // en passant clears the rank for the rook
+        let pos = Position::from_fen("8/8/8/k2pP2R/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mv = Move::en_passant(Square::new(36), Square::new(43));
+        assert!(pos.gives_discovered_check(mv));
+    }
+
+    #[test]
+    fn test_material_balance() {
+        let values = PieceValues::CLASSICAL;
+        assert_eq!(Position::new().material_balance(&values), 0);
+
+        let pos =
+            Position::from_fen("1nbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w - - 0 1").unwrap();
+        assert_eq!(pos.material_balance(&values), 500);
+        assert_eq!(pos.non_pawn_material(Color::White, &values), 2600);
+        assert_eq!(pos.non_pawn_material(Color::Black, &values), 2100);
+    }
+
+    #[test]
+    fn test_kings_in_opposition() {
+        let opposition = |fen| Position::from_fen(fen).unwrap().kings_in_opposition();
+
+        assert!(opposition("8/8/4k3/8/4K3/8/8/8 w - - 0 1"));
+        assert!(opposition("8/8/8/8/2k1K3/8/8/8 b - - 0 1"));
+        assert!(!opposition("8/4k3/8/8/4K3/8/8/8 w - - 0 1"));
+        assert!(!opposition("8/8/5k2/8/4K3/8/8/8 w - - 0 1"));
+        assert!(!Position::new().kings_in_opposition());
+    }
+
+    #[test]
+    fn test_king_ring() {
+        let pos = Position::new();
+        assert_eq!(pos.king_ring(Color::White).count(), 6);
+
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(pos.king_ring(Color::White).count(), 4);
+        assert_eq!(pos.king_ring(Color::Black).count(), 6);
+    }
+
+    #[test]
+    fn test_king_attackers_count() {
+        assert_eq!(Position::new().king_attackers_count(Color::White), 0);
+
+        // knight, bishop and queen aim at the ring, the rook on a1 does not
+        let pos = Position::from_fen("6k1/5ppp/8/4N3/2B5/8/6Q1/R5K1 w - - 0 1").unwrap();
+        assert_eq!(pos.king_attackers_count(Color::White), 3);
+        assert_eq!(pos.king_attackers_count(Color::Black), 0);
+    }
+
+    #[test]
+    fn test_king_danger() {
+        assert_eq!(Position::new().king_danger(Color::Black), 0);
+
+        // queen and knight aim at the black king, the rook on a1 does not
+        let pos = Position::from_fen("6k1/5ppp/8/4N3/8/8/6Q1/R5K1 w - - 0 1").unwrap();
+        assert_eq!(pos.king_danger(Color::Black), 7);
+        assert_eq!(pos.king_danger(Color::White), 0);
+
+        let pos = Position::from_fen("6k1/5ppp/8/4N3/2B5/8/6Q1/R5K1 w - - 0 1").unwrap();
+        assert_eq!(pos.king_danger(Color::Black), 9);
+    }
+
+    #[test]
+    fn test_move_rule_draws() {
+        let mut pos = Position::new();
+
+        for (counter, fifty, seventy_five) in [
+            (99, false, false),
+            (100, true, false),
+            (149, true, false),
+            (150, true, true),
+        ] {
+            pos.set_rule50_counter(counter);
+            assert_eq!(pos.is_fifty_move_draw(), fifty);
+            assert_eq!(pos.is_seventy_five_move_draw(), seventy_five);
+        }
+    }
+
+    #[test]
+    fn test_can_stand_pat() {
+        assert!(Position::new().can_stand_pat());
+
+        let pos = Position::from_fen("4k3/8/8/8/8/8/8/4K2r w - - 0 1").unwrap();
+        assert!(!pos.can_stand_pat());
+    }
+
+    #[test]
+    fn test_check_info() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1",
+            "4k3/8/8/8/1b6/8/3N4/4K2r w - - 0 1",
+        ];
+
+        for fen in fens {
+            let pos = Position::from_fen(fen).unwrap();
+
+            let mut positions = vec![pos];
+            positions.extend(pos.legal_moves().iter().map(|mv| pos.after_move(*mv)));
+
+            for pos in positions {
+                let info = pos.check_info();
+                assert_eq!(info.checkers, pos.checkers(), "{fen}");
+                assert_eq!(info.pinned, pos.pinned(), "{fen}");
+                assert_eq!(
+                    info.checkers.count() > 0,
+                    pos.is_checked(pos.side_to_move()),
+                    "{fen}"
+                );
+
+                // the fast legality check agrees with playing out every move
+                let mut naive = attacks::pseudo_legal_moves(&pos);
+                naive.retain(|mv| !pos.after_move(*mv).is_checked(pos.side_to_move()));
+                assert_eq!(pos.legal_moves(), naive, "{}", pos.fen().unwrap());
+            }
+        }
+
+        let pos = Position::from_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        assert_eq!(pos.pinned(), Bitboard::from_square(Square::new(12)));
+        assert_eq!(pos.check_info().pin_rays.count(), 6);
+    }
+
+    #[test]
+    fn test_repetition_key() {
+        let pos = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let later = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 7 30").unwrap();
+        assert_eq!(pos.repetition_key(), later.repetition_key());
+
+        // exd6 can be played
+        let no_ep = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 2").unwrap();
+        assert_ne!(pos.repetition_key(), no_ep.repetition_key());
+
+        // exd6 would expose the king to the rook
+        let pinned = Position::from_fen("4k3/8/8/K2pP2r/8/8/8/8 w - d6 0 2").unwrap();
+        let pinned_no_ep = Position::from_fen("4k3/8/8/K2pP2r/8/8/8/8 w - - 0 2").unwrap();
+        assert_eq!(pinned.repetition_key(), pinned_no_ep.repetition_key());
+        assert_ne!(pinned.zobrist_hash(), pinned_no_ep.zobrist_hash());
+        assert_eq!(pinned_no_ep.repetition_key(), pinned_no_ep.zobrist_hash());
+    }
+
+    #[test]
+    fn test_hash_after_null() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 20",
+            // the d6 square can be captured on, the e3 square can't
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2",
+            "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            let after = pos.after_null_move();
+
+            assert_eq!(
+                pos.hash_after_null(pos.zobrist_hash()),
+                after.zobrist_hash(),
+                "{}",
+                fen
+            );
+            assert_eq!(after.side_to_move(), !pos.side_to_move());
+            assert_eq!(after.ep_square(), Square::NONE);
+        }
+    }
+
+    #[test]
+    fn test_key128_transposition() {
+        let play = |moves: &[&str]| {
+            let mut pos = Position::new();
+            for san in moves {
+                pos.do_move(Move::from_san(&pos, san).unwrap());
+            }
+            pos
+        };
+
+        let a = play(&["Nf3", "Nf6", "Nc3"]);
+        let b = play(&["Nc3", "Nf6", "Nf3"]);
+        assert_eq!(a.key128(), b.key128());
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+        assert_eq!(a.zobrist_hash(), a.key128() as u64);
+
+        // same placement, different side to move
+        let c = play(&["Nf3", "Nf6", "Nc3", "Ng8", "Ng1", "Nf6"]);
+        assert_ne!(a.key128(), c.key128());
+
+        // the counters are ignored
+        let mut d = a;
+        d.set_rule50_counter(a.rule50_counter() + 10);
+        assert_eq!(a.key128(), d.key128());
+    }
+
+    #[test]
+    fn test_key128_no_collisions() {
+        use std::collections::HashMap;
+
+        let mut keys: HashMap<u128, [u8; 24]> = HashMap::new();
+        let mut seed: u64 = 0x1234_5678;
+
+        for _ in 0..200 {
+            let mut pos = Position::new();
+
+            for _ in 0..80 {
+                let moves = pos.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                pos.do_move(moves[(seed >> 33) as usize % moves.len()]);
+
+                let canonical = pos.canonical_key();
+                let previous = keys.entry(pos.key128()).or_insert(canonical);
+                assert_eq!(*previous, canonical, "{}", pos.fen().unwrap());
+            }
+        }
+
+        // hardly any positions repeat across random games
+        assert!(keys.len() > 10_000);
+    }
+
+    #[test]
+    fn test_from_fen_lenient() {
+        let full = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - bm e5f7;",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ] {
+            assert_eq!(Position::from_fen_lenient(fen).unwrap(), full, "{fen}");
+        }
+
+        let pos = Position::from_fen_lenient("4k3/8/8/8/8/8/8/4K3").unwrap();
+        assert_eq!(pos.fen().unwrap(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        let pos = Position::from_fen_lenient("4k3/8/8/8/8/8/8/4K3 b - - 7 30").unwrap();
+        assert_eq!(pos.rule50_counter(), 7);
+        assert_eq!(pos.ply(), 59);
+
+        assert_eq!(
+            Position::from_fen_lenient(""),
+            Err(PositionError::InvalidFEN)
+        );
+    }
+
+    #[test]
+    fn test_mobility() {
+        let pos = Position::new();
+        assert_eq!(pos.mobility(Color::White), 4);
+        assert_eq!(pos.mobility(Color::White), pos.mobility(Color::Black));
+
+        // the rook attacks 14 squares, the knight on a1 only b3 as c2 is its own pawn
+        let pos = Position::from_fen("4k3/8/8/8/3R4/8/2P5/N3K3 w - - 0 1").unwrap();
+        assert_eq!(pos.mobility(Color::White), 15);
+        assert_eq!(pos.mobility(Color::Black), 0);
+    }
+
+    #[test]
+    fn test_with_side_to_move() {
+        let pos = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 7 40").unwrap();
+
+        let flipped = pos.with_side_to_move(Color::White);
+        assert_eq!(flipped.fen().unwrap(), "4k3/8/8/8/3pP3/8/8/4K3 w - e3 7 40");
+        assert_eq!(flipped.with_side_to_move(Color::Black), pos);
+        assert_eq!(pos.with_side_to_move(Color::Black), pos);
+    }
+
+    #[test]
+    fn test_control() {
+        let control = Position::new().control();
+
+        // the start position is mirror symmetric
+        for sq in 0..64 {
+            assert_eq!(control[sq], -control[sq ^ 56]);
+        }
+
+        // e3 by the d and f pawns, c1 and g1 can't reach it
+        assert_eq!(control[20], 2);
+        assert_eq!(control[28], 0);
+
+        let control = Position::from_fen("4k3/8/8/3p4/4P3/8/8/4K2R w - - 0 1")
+            .unwrap()
+            .control();
+        assert_eq!(control[35], 1);
+        assert_eq!(control[28], -1);
+    }
+
+    #[test]
+    fn test_captured_piece() {
+        let pos = Position::from_fen("r3k3/8/8/3pP3/8/8/8/R3K2R w KQq d6 0 1").unwrap();
+
+        let capture = Move::normal(Square::A1, Square::A8);
+        assert_eq!(pos.captured_piece(capture), Some(Piece::BLACK_ROOK));
+
+        // the pawn on d5, not the empty d6
+        let ep = Move::en_passant(Square::new(36), Square::new(43));
+        assert_eq!(pos.captured_piece(ep), Some(Piece::BLACK_PAWN));
+
+        let castle = Move::castle(Square::E1, Square::H1);
+        assert_eq!(pos.captured_piece(castle), None);
+
+        let quiet = Move::normal(Square::A1, Square::new(8));
+        assert_eq!(pos.captured_piece(quiet), None);
+    }
+
+    #[test]
+    fn test_is_quiet() {
+        assert!(Position::new().is_quiet());
+
+        let calm = "rnbqkbnr/ppp2ppp/3p4/4p3/4P3/3P4/PPP2PPP/RNBQKBNR w KQkq - 0 3";
+        assert!(Position::from_fen(calm).unwrap().is_quiet());
+
+        // exd5 is available
+        let tactical = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+        assert!(!Position::from_fen(tactical).unwrap().is_quiet());
+
+        // in check without any capture
+        let check = "r3k3/8/8/8/8/8/8/K7 w - - 0 1";
+        assert!(!Position::from_fen(check).unwrap().is_quiet());
+
+        // a promotion push
+        let promotion = "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        assert!(!Position::from_fen(promotion).unwrap().is_quiet());
+    }
+
+    #[test]
+    fn test_has_tactical_moves() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+            "rnbqkbnr/ppp2ppp/3p4/4p3/4P3/3P4/PPP2PPP/RNBQKBNR w KQkq - 0 3",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+            "1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/1p6/2R1K3 b - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/8/8/8/8/3k4/8/3K4 b - - 0 1",
+        ];
+
+        for fen in fens {
+            let pos = Position::from_fen(fen).unwrap();
+            let tactical = pos
+                .legal_moves()
+                .iter()
+                .any(|mv| mv.is_promotion() || pos.captured_piece(*mv).is_some());
+
+            assert_eq!(pos.has_tactical_moves(), tactical, "{}", fen);
+        }
+
+        // pseudo-legal, the pinned knight can't take
+        let pinned = "4r1k1/8/8/3p4/8/4N3/8/4K3 w - - 0 1";
+        assert!(Position::from_fen(pinned).unwrap().has_tactical_moves());
+    }
+
+    #[test]
+    fn test_phase_and_taper() {
+        let pos = Position::new();
+        assert_eq!(pos.phase(), 24);
+        assert_eq!(pos.taper(100, -50), 100);
+
+        let pos = Position::from_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").unwrap();
+        assert_eq!(pos.phase(), 0);
+        assert_eq!(pos.taper(100, -50), -50);
+
+        // a rook and a knight each
+        let pos = Position::from_fen("3rk1n1/8/8/8/8/8/8/1N1RK3 w - - 0 1").unwrap();
+        assert_eq!(pos.phase(), 6);
+        assert_eq!(pos.taper(240, 0), 60);
+    }
+
+    #[test]
+    fn test_is_pseudo_legal() {
+        // the knight on e2 is pinned
+        let pos = Position::from_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        let pinned = Move::normal(Square::new(12), Square::new(29));
+
+        assert!(pos.is_pseudo_legal(pinned));
+        assert!(!pos.is_legal(pinned));
+
+        let king = Move::normal(Square::E1, Square::new(3));
+        assert!(pos.is_pseudo_legal(king));
+        assert!(pos.is_legal(king));
+
+        // own piece on the target, a black piece and an unreachable square
+        assert!(!pos.is_pseudo_legal(Move::normal(Square::E1, Square::new(12))));
+        assert!(!pos.is_pseudo_legal(Move::normal(Square::new(52), Square::new(44))));
+        assert!(!pos.is_pseudo_legal(Move::normal(Square::new(12), Square::new(28))));
+        assert!(!pos.is_legal(Move::null()));
+
+        let mut checked = pos;
+        assert_eq!(
+            checked.do_move_checked(pinned),
+            Err(PositionError::IllegalMove)
+        );
+        assert_eq!(checked, pos);
+        assert_eq!(checked.do_move_checked(king), Ok(()));
+        assert_eq!(checked, pos.after_move(king));
+    }
+
+    #[test]
+    fn test_eq_ignore_ep() {
+        // the d4 pawn can capture on e3
+        let with_ep = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+        let without = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+
+        assert_ne!(with_ep, without);
+        assert_ne!(with_ep.canonical_key(), without.canonical_key());
+        assert!(with_ep.eq_ignore_ep(&without));
+        assert!(without.eq_ignore_ep(&with_ep));
+
+        let other = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 w - - 0 1").unwrap();
+        assert!(!with_ep.eq_ignore_ep(&other));
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let ascii = Position::new().to_ascii();
+
+        assert!(ascii.contains("a b c d e f g h"));
+        assert!(ascii.contains("1 | R N B Q K B N R"));
+        assert!(ascii.contains("8 | r n b q k b n r"));
+        assert!(ascii.contains("4 | . . . . . . . ."));
+        assert!(ascii.contains("Side to move: White"));
+        assert!(ascii.contains("Castling: KQkq"));
+        assert!(ascii.contains("En passant: -"));
+
+        let ascii = Position::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1")
+            .unwrap()
+            .to_ascii();
+        assert!(ascii.contains("Side to move: Black"));
+        assert!(ascii.contains("Castling: -"));
+        assert!(ascii.contains("En passant: e3"));
+
+        let unicode = Position::new().to_ascii_with(BoardStyle::Unicode);
+        assert!(unicode.contains("1 | ♖ ♘ ♗ ♕ ♔ ♗ ♘ ♖"));
+        assert!(unicode.contains("7 | ♟ ♟ ♟ ♟ ♟ ♟ ♟ ♟"));
+        assert!(unicode.contains("4 | . . . . . . . ."));
+    }
+
+    #[test]
+    fn test_pieces_of_types() {
+        let pos = Position::new();
+        let heavy = pos.pieces_of_types(&[PieceType::Rook, PieceType::Queen]);
+
+        assert_eq!(heavy.count(), 6);
+        assert!(heavy.sq_set(Square::A1) && heavy.sq_set(Square::D8));
+        assert_eq!(pos.pieces_of_types(&[]), Bitboard::new(0));
+    }
+
+    #[test]
+    fn test_occupancy() {
+        let pos = Position::new();
+
+        assert_eq!(pos.us() | pos.them(), pos.occupied());
+        assert_eq!(pos.us() & pos.them(), Bitboard::new(0));
+        assert_eq!(pos.us(), Bitboard::new(0xFFFF));
+        assert_eq!(pos.empty_squares(), !pos.occupied());
+        assert_eq!(pos.empty_squares().count(), 32);
+
+        let black = pos.with_side_to_move(Color::Black);
+        assert_eq!(black.us(), pos.them());
+        assert_eq!(black.them(), pos.us());
+    }
+
+    #[test]
+    fn test_check_squares() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/3n4/8/1B3R2/8/3P4/Q3K3 w - - 0 1",
+        ] {
+            let pos = Position::from_fen(fen).unwrap();
+            let check_squares = pos.check_squares();
+            let ksq = pos.king_sq(!pos.side_to_move());
+
+            for mv in pos.legal_moves() {
+                assert_eq!(
+                    pos.gives_check(mv),
+                    pos.after_move(mv).is_checked(!pos.side_to_move())
+                );
+
+                if !mv.is_normal() {
+                    continue;
+                }
+
+                let pt = pos.piece_at(mv.from()).piece_type();
+                let after = pos.after_move(mv);
+
+                let direct = if pt == PieceType::Pawn {
+                    attacks::pawn(pos.side_to_move(), mv.to()).sq_set(ksq)
+                } else {
+                    attacks::piece_attacks(pt, mv.to(), after.occupied()).sq_set(ksq)
+                };
+
+                assert_eq!(
+                    check_squares[pt.ordinal() as usize].sq_set(mv.to()),
+                    direct,
+                    "{} {}",
+                    fen,
+                    mv.as_uci()
+                );
+
+                if direct {
+                    assert!(pos.gives_check(mv));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_result() {
+        // fool's mate, White is mated
+        let pos =
+            Position::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+                .unwrap();
+        assert!(pos.is_checkmate());
+        assert_eq!(game_result(&pos), -1);
+
+        // back rank mate, Black is mated
+        let pos = Position::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(pos.is_checkmate());
+        assert_eq!(game_result(&pos), 1);
+
+        let pos = Position::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(pos.is_stalemate());
+        assert!(!pos.is_checkmate());
+        assert_eq!(game_result(&pos), 0);
+
+        assert_eq!(game_result(&Position::new()), 0);
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        let insufficient = |fen| Position::from_fen(fen).unwrap().is_insufficient_material();
+
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"));
+        // c1 and f4 are both dark squares
+        assert!(insufficient("4k3/8/8/8/5b2/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/4b3/8/8/2B1K3 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/8/2N1KN2 w - - 0 1"));
+        assert!(!insufficient("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+        assert!(!Position::new().is_insufficient_material());
+    }
+
+    #[test]
+    fn test_cannot_force_mate() {
+        let cannot = |fen| Position::from_fen(fen).unwrap().cannot_force_mate();
+
+        assert!(cannot("4k3/8/8/8/8/8/8/2N1KN2 w - - 0 1"));
+        assert!(cannot("2n1kn2/8/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(cannot("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"));
+        assert!(!cannot("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1"));
+        assert!(!cannot("4kn2/8/8/8/8/8/8/4KN2 w - - 0 1"));
+        assert!(!cannot("4k3/8/8/8/8/8/8/1NN1KN2 w - - 0 1"));
+        assert!(!cannot("4k3/8/8/8/8/8/4P3/2N1KN2 w - - 0 1"));
+        assert!(!Position::new().cannot_force_mate());
+    }
+
+    #[test]
+    fn test_attackers_to() {
+        let pos = Position::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        for sq in 0..64 {
+            let sq = Square::new(sq);
+            for c in [Color::White, Color::Black] {
+                assert_eq!(
+                    pos.attackers(sq, c),
+                    pos.attackers_to(sq, c, pos.occupied())
+                );
+                assert_eq!(
+                    pos.is_attacked(sq, c),
+                    pos.is_attacked_with(sq, c, pos.occupied())
+                );
+            }
+        }
+
+        // the rooks on a1 and a8 see each other once a2 and a7 are gone
+        let pos = Position::from_fen("r3k3/p7/8/8/8/8/P7/R3K3 w - - 0 1").unwrap();
+        let a8 = Square::new(56);
+        assert_eq!(pos.attackers(a8, Color::White), Bitboard::new(0));
+
+        let occupied = pos.occupied()
+            & !Bitboard::from_square(Square::new(8))
+            & !Bitboard::from_square(Square::new(48));
+        assert_eq!(
+            pos.attackers_to(a8, Color::White, occupied),
+            Bitboard::from_square(Square::A1)
+        );
+
+        // a piece removed from the occupancy doesn't attack
+        let occupied = occupied & !Bitboard::from_square(Square::A1);
+        assert!(!pos.is_attacked_with(a8, Color::White, occupied));
+    }
+
+    #[test]
+    fn test_pieces_on_same_line() {
+        let pos = Position::from_fen("4r1k1/8/8/4n3/8/4B3/8/4K3 w - - 0 1").unwrap();
+        let (e3, e5) = (Square::new(20), Square::new(36));
+
+        let line = pos.pieces_on_same_line(Square::E8, Square::E1).unwrap();
+        assert_eq!(line, [Square::E1, e3, e5, Square::E8].into_iter().collect());
+        assert_eq!(
+            line & attacks::between(Square::E8, Square::E1),
+            [e3, e5].into_iter().collect()
+        );
+
+        // the g8 king is on the eighth rank with the rook
+        assert_eq!(
+            pos.pieces_on_same_line(Square::A8, Square::E8),
+            Some([Square::E8, Square::G8].into_iter().collect())
+        );
+
+        assert_eq!(pos.pieces_on_same_line(Square::E1, Square::G8), None);
+        assert_eq!(pos.pieces_on_same_line(Square::E1, Square::E1), None);
+    }
+
+    #[test]
+    fn test_capture_sequence_on() {
+        // d5 is defended by a pawn, then the rook and the queen on b7,
+        // the white rook joins behind the queen
+        let pos = Position::from_fen("3r2k1/1q6/4p3/3p4/4P3/2N5/3Q4/3R2K1 w - - 0 1").unwrap();
+
+        assert_eq!(
+            pos.capture_sequence_on(Square::new(35)),
+            vec![
+                (Color::White, PieceType::Pawn),
+                (Color::Black, PieceType::Pawn),
+                (Color::White, PieceType::Knight),
+                (Color::Black, PieceType::Rook),
+                (Color::White, PieceType::Queen),
+                (Color::Black, PieceType::Queen),
+                (Color::White, PieceType::Rook),
+            ]
+        );
+
+        // the king can't take a defended piece
+        let pos = Position::from_fen("3rk3/8/8/8/8/8/3r4/4K3 w - - 0 1").unwrap();
+        assert!(pos.capture_sequence_on(Square::new(11)).is_empty());
+
+        assert!(Position::new()
+            .capture_sequence_on(Square::new(35))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_see_ge() {
+        // the pawn on d5 is defended by e6
+        let pos = Position::from_fen("4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let qxd5 = Move::normal(Square::new(11), Square::new(35));
+        assert!(!pos.see_ge(qxd5, 0));
+        assert!(pos.see_ge(qxd5, -800));
+        assert!(!pos.see_ge(qxd5, -799));
+
+        // rook for rook
+        let pos = Position::from_fen("3rk3/8/8/8/8/8/8/3RK3 w - - 0 1").unwrap();
+        let rxd8 = Move::normal(Square::D1, Square::D8);
+        assert!(pos.see_ge(rxd8, 0));
+        assert!(!pos.see_ge(rxd8, 1));
+
+        // the rook is defended by the x-ray through the queen
+        let pos = Position::from_fen("3rk3/8/8/8/8/8/3Q4/3RK3 w - - 0 1").unwrap();
+        let qxd8 = Move::normal(Square::new(11), Square::D8);
+        assert!(pos.see_ge(qxd8, 0));
+        assert!(pos.see_ge(qxd8, 500));
+        assert!(!pos.see_ge(qxd8, 501));
+
+        assert!(Position::new().see_ge(Move::normal(Square::G1, Square::new(21)), 0));
+    }
+
+    #[test]
+    fn test_passed_pawns() {
+        // only the d5 pawn is passed, the a7 pawn stops a2 and b2
+        let pos = Position::from_fen("4k3/p7/8/3P4/8/8/PP6/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            pos.passed_pawns(Color::White),
+            Bitboard::from_square(Square::new(35))
+        );
+
+        // a7 is stopped by a2 and b2
+        assert_eq!(pos.passed_pawns(Color::Black), Bitboard::new(0));
+
+        assert_eq!(Position::new().passed_pawns(Color::White), Bitboard::new(0));
+
+        // a pawn guarding the square in front is enough
+        let pos = Position::from_fen("4k3/8/2p5/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(pos.passed_pawns(Color::White), Bitboard::new(0));
+
+        // a pawn level with an enemy pawn on the adjacent file is passed
+        let pos = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(
+            pos.passed_pawns(Color::White),
+            Bitboard::from_square(Square::new(36))
+        );
+        assert_eq!(
+            pos.passed_pawns(Color::Black),
+            Bitboard::from_square(Square::new(35))
+        );
+    }
+
+    #[test]
+    fn test_make_unmake() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 17",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r1b1k2r/1P3ppp/8/8/8/8/p4PPP/R3K1NR b KQkq - 0 20",
+            "8/2p5/3p4/KP5r/1R3pPk/8/4P3/8 b - g3 0 1",
+        ];
+
+        for fen in fens {
+            let original = Position::from_fen(fen).unwrap();
+            let mut pos = original;
+
+            for mv in original.legal_moves() {
+                let undo = pos.make(mv);
+                assert_eq!(pos, original.after_move(mv), "{} {:?}", fen, mv);
+
+                pos.unmake(mv, undo);
+                assert_eq!(pos, original, "{} {:?}", fen, mv);
+                assert_eq!(pos.zobrist_hash(), original.zobrist_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn test_material_signature() {
+        let kqkr = Position::from_fen("4k3/8/8/8/8/8/3r4/Q3K3 w - - 0 1").unwrap();
+        assert_eq!(kqkr.material_signature(), "KQKR");
+
+        let krkq = Position::from_fen("4k3/8/8/8/8/8/3q4/R3K3 w - - 0 1").unwrap();
+        assert_eq!(krkq.material_signature(), "KQKR");
+        assert_ne!(kqkr.material_key(), krkq.material_key());
+
+        let start = Position::new();
+        assert_eq!(
+            start.material_signature(),
+            "KQRRBBNNPPPPPPPPKQRRBBNNPPPPPPPP"
+        );
+        assert_eq!(start.material_key(), 0x12228_12228);
+        assert_eq!(
+            start
+                .after_move(Move::normal(Square::new(12), Square::new(28)))
+                .material_key(),
+            start.material_key()
+        );
+    }
+
+    #[test]
+    fn test_legal_moves_from() {
+        let pos = Position::new();
+
+        let e2 = pos.legal_moves_from(Square::new(12));
+        assert_eq!(e2.len(), 2);
+        assert!(e2.contains(&Move::normal(Square::new(12), Square::new(20))));
+        assert!(e2.contains(&Move::normal(Square::new(12), Square::new(28))));
+
+        assert!(pos.legal_moves_from(Square::C1).is_empty());
+        assert!(pos.legal_moves_from(Square::new(28)).is_empty());
+        assert!(pos.legal_moves_from(Square::new(52)).is_empty());
+    }
+
+    #[test]
+    fn test_legal_move_count() {
+        for line in include_str!("../../test/standard.epd").lines() {
+            let fen = line.split(';').next().unwrap().trim();
+            if fen.is_empty() {
+                continue;
+            }
+
+            let pos = Position::from_fen(fen).unwrap();
+            assert_eq!(
+                pos.legal_move_count() as usize,
+                pos.legal_moves().len(),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        assert_eq!(Position::new().legal_moves().len(), 20);
+
+        // the pinned knight can't move
+        let pos = Position::from_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        assert!(pos.legal_moves().iter().all(|mv| mv.from() == Square::E1));
     }
 }
@@ -0,0 +1,71 @@
+/// Random keys for Zobrist hashing, generated at compile time from a seed.
+pub struct ZobristKeys {
+    /// Indexed by piece id and square
+    pub pieces: [[u64; 64]; 12],
+    pub side: u64,
+    /// One key per castling right, KQkq
+    pub castling: [u64; 4],
+    pub ep_file: [u64; 8],
+}
+
+impl ZobristKeys {
+    pub const fn new(seed: u64) -> Self {
+        let mut state = seed;
+
+        let mut pieces = [[0u64; 64]; 12];
+        let mut pc = 0;
+        while pc < 12 {
+            let mut sq = 0;
+            while sq < 64 {
+                state = splitmix64(state);
+                pieces[pc][sq] = mix(state);
+                sq += 1;
+            }
+            pc += 1;
+        }
+
+        state = splitmix64(state);
+        let side = mix(state);
+
+        let mut castling = [0u64; 4];
+        let mut i = 0;
+        while i < 4 {
+            state = splitmix64(state);
+            castling[i] = mix(state);
+            i += 1;
+        }
+
+        let mut ep_file = [0u64; 8];
+        let mut i = 0;
+        while i < 8 {
+            state = splitmix64(state);
+            ep_file[i] = mix(state);
+            i += 1;
+        }
+
+        Self {
+            pieces,
+            side,
+            castling,
+            ep_file,
+        }
+    }
+}
+
+/// Keys used by `Position::zobrist_hash` and the lower half of `Position::key128`
+pub static KEYS: ZobristKeys = ZobristKeys::new(0x9E37_79B9_7F4A_7C15);
+
+/// Independent keys for the upper half of `Position::key128`
+pub static KEYS_HIGH: ZobristKeys = ZobristKeys::new(0xD1B5_4A32_D192_ED03);
+
+// SplitMix64, the state advances by a constant and `mix` turns it into the output
+const fn splitmix64(state: u64) -> u64 {
+    state.wrapping_add(0x9E37_79B9_7F4A_7C15)
+}
+
+const fn mix(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
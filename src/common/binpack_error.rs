@@ -5,8 +5,14 @@ use thiserror::Error;
 pub enum BinpackError {
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
-    #[error("Invalid magic bytes")]
-    InvalidMagic,
+    #[error("Invalid magic bytes {0:?}, expected \"BINP\"")]
+    BadMagic([u8; 4]),
+    #[error("File is gzip compressed, decompress it or read it with `open_auto` (gzip feature)")]
+    GzipCompressed,
+    #[error("File ends inside a chunk, {expected} bytes expected but only {actual} left")]
+    ShortChunk { expected: u64, actual: u64 },
+    #[error("Chunk size of {0} bytes is larger than supported, malformed file?")]
+    BadChunkSize(u32),
     #[error("Invalid format: {0}")]
     InvalidFormat(String),
 }
@@ -15,6 +15,9 @@ pub struct CompressedMove {
     // 6 bits for to square
     // 2 bits for promoted piece type
     //    0 if not a promotion
+    //
+    // the null move (`Move::null()`, from == to) is encoded as 0,
+    // which is otherwise the invalid move a1a1
     packed: u16,
 }
 
@@ -37,11 +40,17 @@ impl CompressedMove {
         Self { packed: 0 }
     }
 
-    // pub const fn from_ordinal(data: u16) -> Self {
-    //     Self { packed: data }
-    // }
+    /// Create a compressed move from its raw 16 bit value as stored in binpacks.
+    /// From the most significant bit: 2 bits move type (normal, promotion,
+    /// castle, en passant), 6 bits from square, 6 bits to square and 2 bits
+    /// promoted piece type from knight (0) to queen (3), 0 for other moves.
+    /// Castling is encoded as king captures rook, the null move as 0.
+    pub const fn from_value(value: u16) -> Self {
+        Self { packed: value }
+    }
 
-    // move must be either valid or a null move
+    /// Compress a move, it must be either valid or a null move.
+    /// A null move (from == to) is encoded as 0 and decompresses to `Move::null()`.
     pub fn from_move(move_: Move) -> Self {
         let mut packed = 0;
 
@@ -54,7 +63,7 @@ impl CompressedMove {
                 | ((move_.from().index() as u16) << (16 - 2 - 6))
                 | ((move_.to().index() as u16) << (16 - 2 - 6 - 6));
 
-            if move_.mtype() == MoveType::Promotion {
+            if move_.is_promotion() {
                 debug_assert!(move_.promoted_piece() != Piece::none());
 
                 packed |= (move_.promoted_piece().piece_type() as u16) - (PieceType::Knight as u16);
@@ -71,9 +80,10 @@ impl CompressedMove {
         data[1] = (self.packed & 0xFF) as u8;
     }
 
-    // pub const fn packed(&self) -> u16 {
-    //     self.packed
-    // }
+    /// The raw 16 bit value, see [`Self::from_value`] for the layout
+    pub const fn value(&self) -> u16 {
+        self.packed
+    }
 
     pub const fn move_type(&self) -> MoveType {
         MoveType::from_ordinal((self.packed >> (16 - 2)) as u8)
@@ -165,6 +175,22 @@ mod tests {
         assert_eq!(expected, compressed.decompress());
     }
 
+    #[test]
+    fn test_null_move() {
+        let compressed = CompressedMove::from_move(Move::null());
+
+        assert_eq!(compressed, CompressedMove::new());
+        assert_eq!(Move::null(), compressed.decompress());
+
+        let mut data = [0xff; 2];
+        compressed.write_to_big_endian(&mut data);
+        assert_eq!(data, [0, 0]);
+        assert_eq!(
+            Move::null(),
+            CompressedMove::read_from_big_endian(&data).decompress()
+        );
+    }
+
     #[test]
     fn test_member_functions() {
         let expected = Move::new(
@@ -184,4 +210,33 @@ mod tests {
             compressed.promoted_piece()
         );
     }
+
+    #[test]
+    fn test_value_roundtrip() {
+        let moves = [
+            Move::normal(Square::new(12), Square::new(28)),
+            Move::new(
+                Square::new(9),
+                Square::new(0),
+                MoveType::Promotion,
+                Piece::new(PieceType::Knight, Color::Black),
+            ),
+            Move::castle(Square::E8, Square::A8),
+            Move::en_passant(Square::new(36), Square::new(43)),
+            Move::null(),
+        ];
+
+        for mv in moves {
+            assert_eq!(
+                CompressedMove::from_value(CompressedMove::compress(&mv).value()).decompress(),
+                mv
+            );
+        }
+
+        // e2e4: normal, from 12, to 28
+        assert_eq!(
+            CompressedMove::compress(&moves[0]).value(),
+            (12 << 8) | (28 << 2)
+        );
+    }
 }
@@ -19,6 +19,8 @@ impl CompressedPosition {
         std::mem::size_of::<CompressedPosition>()
     }
 
+    /// Read the 24 byte position with the occupancy stored big-endian,
+    /// the layout used by Stockfish binpacks.
     pub fn read_from_big_endian(data: &[u8]) -> Self {
         debug_assert!(data.len() >= 24);
 
@@ -42,6 +44,8 @@ impl CompressedPosition {
         }
     }
 
+    /// Write the 24 byte position with the occupancy stored big-endian,
+    /// the layout used by Stockfish binpacks.
     pub fn write_to_big_endian(&self, data: &mut [u8]) {
         let occupied = self.occupied.bits();
         data[0] = (occupied >> 56) as u8;
@@ -55,6 +59,31 @@ impl CompressedPosition {
         data[8..24].copy_from_slice(&self.packed_state[..16]);
     }
 
+    /// Read the 24 byte position with the occupancy stored little-endian, as
+    /// written by some external tooling. The packed piece state is the same
+    /// in both layouts, binpacks always use [`Self::read_from_big_endian`].
+    pub fn read_from_little_endian(data: &[u8]) -> Self {
+        debug_assert!(data.len() >= 24);
+
+        let mut occupied = [0u8; 8];
+        occupied.copy_from_slice(&data[..8]);
+
+        let mut packed_state = [0u8; 16];
+        packed_state.copy_from_slice(&data[8..24]);
+
+        Self {
+            occupied: Bitboard::new(u64::from_le_bytes(occupied)),
+            packed_state,
+        }
+    }
+
+    /// Write the 24 byte position with the occupancy stored little-endian,
+    /// the counterpart of [`Self::read_from_little_endian`].
+    pub fn write_to_little_endian(&self, data: &mut [u8]) {
+        data[..8].copy_from_slice(&self.occupied.bits().to_le_bytes());
+        data[8..24].copy_from_slice(&self.packed_state[..16]);
+    }
+
     pub fn decompress(&self) -> Position {
         let mut pos = Position::empty();
         pos.set_castling_rights(CastlingRights::NONE);
@@ -119,6 +148,9 @@ impl CompressedPosition {
         pos
     }
 
+    /// Compress the piece placement, side to move, castling rights and en passant
+    /// square. The move counters are not part of the compressed form, positions
+    /// that only differ in them compress identically.
     pub fn compress(pos: &Position) -> Self {
         let mut compressed = CompressedPosition {
             occupied: pos.occupied(),
@@ -253,6 +285,28 @@ mod tests {
         assert_eq!(data, new_data);
     }
 
+    #[test]
+    fn test_little_endian() {
+        let pos =
+            Position::from_fen("1r3rk1/p2qnpb1/6pp/P1p1p3/3nN3/2QP2P1/R3PPBP/2B2RK1 b - - 0 1")
+                .unwrap();
+        let compressed_pos = CompressedPosition::compress(&pos);
+
+        let mut little = [0; 24];
+        compressed_pos.write_to_little_endian(&mut little);
+        assert_eq!(
+            CompressedPosition::read_from_little_endian(&little),
+            compressed_pos
+        );
+
+        // only the occupancy bytes are swapped
+        let mut big = [0; 24];
+        compressed_pos.write_to_big_endian(&mut big);
+        assert_ne!(big, little);
+        little[..8].reverse();
+        assert_eq!(big, little);
+    }
+
     #[test]
     fn test_compress_decompress() {
         let pos =
@@ -290,4 +344,20 @@ mod tests {
 
         assert_eq!(position_without_fmt, decompressed_pos);
     }
+
+    #[test]
+    fn test_compress_ignores_counters() {
+        let early = Position::from_fen("2r3k1/4bpp1/2Q1p2P/p3P3/1p6/4B1P1/P1r2PK1/3R1R2 b - - 0 1")
+            .unwrap();
+        let late =
+            Position::from_fen("2r3k1/4bpp1/2Q1p2P/p3P3/1p6/4B1P1/P1r2PK1/3R1R2 b - - 37 90")
+                .unwrap();
+
+        assert_ne!(early, late);
+        assert_eq!(
+            CompressedPosition::compress(&early),
+            CompressedPosition::compress(&late)
+        );
+        assert_eq!(CompressedPosition::compress(&late).decompress(), early);
+    }
 }
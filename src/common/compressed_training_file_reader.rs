@@ -2,9 +2,10 @@ use std::io::{Read, Seek, SeekFrom};
 
 use super::binpack_error::{BinpackError, Result};
 
-const HEADER_SIZE: usize = 8;
+pub const HEADER_SIZE: usize = 8;
 const MAX_CHUNK_SIZE: u32 = 100 * 1024 * 1024;
 const MAGIC: &[u8; 4] = b"BINP";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 #[derive(Debug)]
 struct Header {
@@ -15,13 +16,19 @@ struct Header {
 pub struct CompressedTrainingDataFileReader<T: Read + Seek> {
     file: T,
     read_bytes: u64,
+    file_size: u64,
 }
 
 impl<T: Read + Seek> CompressedTrainingDataFileReader<T> {
-    pub fn new(file: T) -> std::io::Result<Self> {
+    pub fn new(mut file: T) -> std::io::Result<Self> {
+        let pos = file.stream_position()?;
+        let file_size = file.seek(SeekFrom::End(0))?;
+        file.seek(SeekFrom::Start(pos))?;
+
         Ok(Self {
             file,
-            read_bytes: 0,
+            read_bytes: pos,
+            file_size,
         })
     }
 
@@ -33,6 +40,11 @@ impl<T: Read + Seek> CompressedTrainingDataFileReader<T> {
         self.read_bytes
     }
 
+    /// Size of the underlying file in bytes
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
     pub fn has_next_chunk(&mut self) -> bool {
         if let Ok(pos) = self.file.stream_position() {
             if let Ok(len) = self.file.seek(SeekFrom::End(0)) {
@@ -48,7 +60,7 @@ impl<T: Read + Seek> CompressedTrainingDataFileReader<T> {
     pub fn read_next_chunk(&mut self) -> Result<Vec<u8>> {
         let header = self.read_chunk_header()?;
         let mut data = vec![0u8; header.chunk_size as usize];
-        self.file.read_exact(&mut data)?;
+        self.read_exact_in_chunk(&mut data)?;
         self.read_bytes += header.chunk_size as u64;
         Ok(data)
     }
@@ -56,7 +68,7 @@ impl<T: Read + Seek> CompressedTrainingDataFileReader<T> {
     pub fn read_next_chunk_into(&mut self, buffer: &mut Vec<u8>) -> Result<()> {
         let header = self.read_chunk_header()?;
         buffer.resize(header.chunk_size as usize, 0);
-        self.file.read_exact(buffer)?;
+        self.read_exact_in_chunk(buffer)?;
         self.read_bytes += header.chunk_size as u64;
         Ok(())
     }
@@ -64,25 +76,40 @@ impl<T: Read + Seek> CompressedTrainingDataFileReader<T> {
     fn read_chunk_header(&mut self) -> Result<Header> {
         let mut buf = [0u8; HEADER_SIZE];
 
-        match self.file.read_exact(&mut buf) {
-            Ok(_) => (),
-            Err(_) => return Err(BinpackError::InvalidMagic),
+        self.read_exact_in_chunk(&mut buf)?;
+
+        // only the start of the file tells it is gzip, not a corrupt header later on
+        if self.read_bytes == 0 && buf[0..2] == GZIP_MAGIC {
+            return Err(BinpackError::GzipCompressed);
         }
 
         self.read_bytes += HEADER_SIZE as u64;
 
         if &buf[0..4] != MAGIC {
-            return Err(BinpackError::InvalidMagic);
+            return Err(BinpackError::BadMagic(buf[0..4].try_into().unwrap()));
         }
 
         let chunk_size = u32::from_le_bytes(buf[4..8].try_into().unwrap());
 
         if chunk_size > MAX_CHUNK_SIZE {
-            return Err(BinpackError::InvalidFormat(
-                "Chunk size larger than supported. Malformed file?".to_string(),
-            ));
+            return Err(BinpackError::BadChunkSize(chunk_size));
         }
 
         Ok(Header { chunk_size })
     }
+
+    // A file ending inside a chunk is malformed rather than an IO failure
+    fn read_exact_in_chunk(&mut self, buffer: &mut [u8]) -> Result<()> {
+        let pos = self.file.stream_position()?;
+
+        match self.file.read_exact(buffer) {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Err(BinpackError::ShortChunk {
+                    expected: buffer.len() as u64,
+                    actual: self.file_size.saturating_sub(pos),
+                })
+            }
+            result => Ok(result?),
+        }
+    }
 }
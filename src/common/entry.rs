@@ -1,13 +1,37 @@
 use std::fmt;
 
-use crate::chess::{position::Position, r#move::Move};
+use thiserror::Error;
+
+use crate::chess::{
+    position::{Position, PositionError},
+    r#move::Move,
+};
 
 use super::{
     arithmetic::{signed_to_unsigned, unsigned_to_signed},
+    binpack_error::BinpackError,
     compressed_move::CompressedMove,
     compressed_position::CompressedPosition,
 };
 
+/// The largest score magnitude accepted by [`TrainingDataEntry::validate`],
+/// a mate score in Stockfish
+const MAX_SCORE: i16 = 32000;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum EntryError {
+    #[error("Invalid position: {0:?}")]
+    InvalidPosition(PositionError),
+    #[error("Move {0} is not legal in the position")]
+    IllegalMove(String),
+    #[error("Score {0} is out of range")]
+    ScoreOutOfRange(i16),
+    #[error("Ply {ply} doesn't match the ply {position_ply} of the position")]
+    PlyMismatch { ply: u16, position_ply: u16 },
+    #[error("Result {0} is not one of -1, 0 or 1")]
+    InvalidResult(i16),
+}
+
 /// A single training data entry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TrainingDataEntry {
@@ -25,11 +49,115 @@ pub struct TrainingDataEntry {
 }
 
 impl TrainingDataEntry {
+    /// Check if `other` follows this entry in the same game.
+    /// An entry with a null move, i.e. without a best move, is never continued.
     pub fn is_continuation(&self, &other: &TrainingDataEntry) -> bool {
         self.result == -other.result
             && self.ply + 1 == other.ply
+            && self.mv != Move::null()
             && self.pos.after_move(self.mv) == other.pos
     }
+
+    /// Check that the entry is consistent: the position is valid, the move is legal
+    /// or null, the score is within ±32000, the ply matches the position
+    /// and the result is -1, 0 or 1.
+    pub fn validate(&self) -> Result<(), EntryError> {
+        self.pos.validate().map_err(EntryError::InvalidPosition)?;
+
+        if self.mv != Move::null() && !self.pos.is_legal(self.mv) {
+            return Err(EntryError::IllegalMove(self.mv.as_uci()));
+        }
+
+        if self.score.unsigned_abs() > MAX_SCORE as u16 {
+            return Err(EntryError::ScoreOutOfRange(self.score));
+        }
+
+        if self.ply != self.pos.ply() {
+            return Err(EntryError::PlyMismatch {
+                ply: self.ply,
+                position_ply: self.pos.ply(),
+            });
+        }
+
+        if !(-1..=1).contains(&self.result) {
+            return Err(EntryError::InvalidResult(self.result));
+        }
+
+        Ok(())
+    }
+
+    /// Compare two entries while ignoring the move counters of the positions,
+    /// see [`Position::canonical_key`].
+    pub fn semantic_eq(&self, other: &TrainingDataEntry) -> bool {
+        self.pos.canonical_key() == other.pos.canonical_key()
+            && self.mv == other.mv
+            && self.score == other.score
+            && self.ply == other.ply
+            && self.result == other.result
+    }
+
+    /// The ply and result as packed into the stem, the ply in the low 14 bits
+    /// and the zigzag encoded result in the top 2 bits. There is no spare bit,
+    /// the fourth result value 3, read back as a result of -2, is the only
+    /// room for custom metadata such as an adjudication marker.
+    pub fn raw_ply_result(&self) -> u16 {
+        (self.ply & 0x3FFF) | (signed_to_unsigned(self.result) << 14)
+    }
+
+    /// Set the ply and result from their packed form, see [`Self::raw_ply_result`].
+    pub fn set_raw_ply_result(&mut self, pr: u16) {
+        self.ply = pr & 0x3FFF;
+        self.result = unsigned_to_signed(pr >> 14);
+    }
+
+    /// Returns the move in SAN, e.g. `Nf3` instead of `g1f3`.
+    pub fn best_move_san(&self) -> String {
+        self.mv.as_san(&self.pos)
+    }
+
+    /// Convert to a record of primitive fields, e.g. to pass it over FFI.
+    pub fn as_flat(&self) -> FlatEntry {
+        let packed = PackedTrainingDataEntry::from_entry(self);
+        let pos_size = CompressedPosition::byte_size();
+
+        FlatEntry {
+            pos: packed.data[..pos_size].try_into().unwrap(),
+            mv: packed.read_u16_be(pos_size),
+            score: self.score,
+            ply: self.ply,
+            result: self.result,
+            rule50: self.pos.rule50_counter(),
+        }
+    }
+
+    /// Inverse of [`TrainingDataEntry::as_flat`].
+    pub fn from_flat(flat: &FlatEntry) -> Self {
+        let mut pos = CompressedPosition::read_from_big_endian(&flat.pos).decompress();
+        pos.set_ply(flat.ply);
+        pos.set_rule50_counter(flat.rule50);
+
+        TrainingDataEntry {
+            pos,
+            mv: CompressedMove::from_value(flat.mv).decompress(),
+            score: flat.score,
+            ply: flat.ply,
+            result: flat.result,
+        }
+    }
+}
+
+/// A training data entry as a plain copyable record with a stable layout.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlatEntry {
+    /// The position as a big endian `CompressedPosition`, without move counters
+    pub pos: [u8; 24],
+    /// The move as a `CompressedMove`, 0 for a null move
+    pub mv: u16,
+    pub score: i16,
+    pub ply: u16,
+    pub result: i16,
+    pub rule50: u16,
 }
 
 impl fmt::Display for TrainingDataEntry {
@@ -46,6 +174,17 @@ impl fmt::Display for TrainingDataEntry {
     }
 }
 
+/// Byte order of the stem score of a packed entry.
+/// The score itself is always stored zigzag encoded, see [`unsigned_to_signed`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreEncoding {
+    /// Big endian, as written by Stockfish
+    #[default]
+    BigEndianSigned,
+    /// Little endian, as found in some third-party binpacks
+    LittleEndianSigned,
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct PackedTrainingDataEntry {
     pub data: [u8; 32],
@@ -53,17 +192,17 @@ pub struct PackedTrainingDataEntry {
 
 /// A packed training data entry.
 impl PackedTrainingDataEntry {
+    /// Panics if the slice is shorter than 32 bytes, see the `TryFrom<&[u8]>` implementation.
     pub fn from_slice(slice: &[u8]) -> Self {
-        PackedTrainingDataEntry {
-            data: slice.try_into().unwrap(),
-        }
+        Self::try_from(slice).unwrap()
     }
 
     pub fn byte_size() -> usize {
         std::mem::size_of::<PackedTrainingDataEntry>()
     }
 
-    pub fn unpack_entry(&self) -> TrainingDataEntry {
+    /// Unpack the entry, reading the score with the given byte order.
+    pub fn unpack_entry_with(&self, score_encoding: ScoreEncoding) -> TrainingDataEntry {
         let mut offset = 0;
 
         // Read and decompress position
@@ -80,30 +219,34 @@ impl PackedTrainingDataEntry {
 
         // Read score
         // EBNF: Score
-        let score = unsigned_to_signed(self.read_u16_be(offset));
+        let score = unsigned_to_signed(match score_encoding {
+            ScoreEncoding::BigEndianSigned => self.read_u16_be(offset),
+            ScoreEncoding::LittleEndianSigned => self.read_u16_be(offset).swap_bytes(),
+        });
         offset += 2;
 
         // Read ply and result (packed together)
         // EBNF: PlyResult
         let pr = self.read_u16_be(offset);
-        let ply = pr & 0x3FFF;
-        let result = unsigned_to_signed(pr >> 14);
         offset += 2;
 
-        // Set position's ply
-        pos.set_ply(ply);
-
         // Read and set rule50 counter
         // EBNF: Rule50
         pos.set_rule50_counter(self.read_u16_be(offset));
 
-        TrainingDataEntry {
+        let mut entry = TrainingDataEntry {
             pos,
             mv,
             score,
-            ply,
-            result,
-        }
+            ply: 0,
+            result: 0,
+        };
+        entry.set_raw_ply_result(pr);
+
+        // Set position's ply
+        entry.pos.set_ply(entry.ply);
+
+        entry
     }
 
     pub fn from_entry(entry: &TrainingDataEntry) -> Self {
@@ -123,7 +266,7 @@ impl PackedTrainingDataEntry {
         offset += CompressedMove::byte_size();
 
         // Pack ply and result
-        let pr = entry.ply | (signed_to_unsigned(entry.result) << 14);
+        let pr = entry.raw_ply_result();
         packed.data[offset] = (signed_to_unsigned(entry.score) >> 8) as u8;
         offset += 1;
         packed.data[offset] = signed_to_unsigned(entry.score) as u8;
@@ -146,6 +289,27 @@ impl PackedTrainingDataEntry {
     }
 }
 
+impl TryFrom<&[u8]> for PackedTrainingDataEntry {
+    type Error = BinpackError;
+
+    /// Read a packed entry from the first 32 bytes of the slice.
+    fn try_from(slice: &[u8]) -> Result<Self, Self::Error> {
+        let size = Self::byte_size();
+
+        if slice.len() < size {
+            return Err(BinpackError::InvalidFormat(format!(
+                "Packed entry needs {} bytes, got {}",
+                size,
+                slice.len()
+            )));
+        }
+
+        Ok(PackedTrainingDataEntry {
+            data: slice[..size].try_into().unwrap(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::chess::{coords::Square, piece::Piece, r#move::MoveType};
@@ -161,7 +325,7 @@ mod test {
 
         let packed_entry = PackedTrainingDataEntry::from_slice(&data);
 
-        let entry = packed_entry.unpack_entry();
+        let entry = packed_entry.unpack_entry_with(ScoreEncoding::default());
 
         let expected = TrainingDataEntry {
             pos: Position::from_fen(
@@ -180,6 +344,136 @@ mod test {
         };
 
         assert_eq!(entry, expected);
+
+        // the rook on b8 could also move to c8
+        assert_eq!(entry.best_move_san(), "Rfc8");
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let entry = TrainingDataEntry {
+            pos: Position::from_fen("1q5b/1r5k/4p2p/1b2P1pN/3p4/6PP/1nP3B1/1Q2B1K1 w - - 0 35")
+                .unwrap(),
+            mv: Move::new(
+                Square::new(10),
+                Square::new(26),
+                MoveType::Normal,
+                Piece::none(),
+            ),
+            score: -201,
+            ply: 68,
+            result: 0,
+        };
+
+        let mut other = entry;
+        other.pos.set_rule50_counter(7);
+
+        assert_ne!(entry, other);
+        assert!(entry.semantic_eq(&other));
+
+        other.score = 0;
+        assert!(!entry.semantic_eq(&other));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut entry = PackedTrainingDataEntry::from_slice(&[
+            98, 121, 192, 21, 24, 76, 241, 100, 100, 106, 0, 4, 8, 48, 2, 17, 17, 145, 19, 117,
+            247, 0, 0, 0, 61, 232, 0, 253, 0, 39, 0, 2,
+        ])
+        .unpack_entry_with(ScoreEncoding::default());
+        assert_eq!(entry.validate(), Ok(()));
+
+        entry.ply = 40;
+        assert_eq!(
+            entry.validate(),
+            Err(EntryError::PlyMismatch {
+                ply: 40,
+                position_ply: 39
+            })
+        );
+        entry.ply = 39;
+
+        entry.mv = Move::null();
+        assert_eq!(entry.validate(), Ok(()));
+
+        entry.mv = Move::new(
+            Square::new(61),
+            Square::new(5),
+            MoveType::Normal,
+            Piece::none(),
+        );
+        assert!(matches!(entry.validate(), Err(EntryError::IllegalMove(_))));
+        entry.mv = Move::null();
+
+        entry.score = -32002;
+        assert_eq!(entry.validate(), Err(EntryError::ScoreOutOfRange(-32002)));
+        entry.score = 0;
+
+        entry.result = 2;
+        assert_eq!(entry.validate(), Err(EntryError::InvalidResult(2)));
+    }
+
+    #[test]
+    fn test_packed_training_data_entry_try_from() {
+        let data = [0u8; 10];
+        assert!(matches!(
+            PackedTrainingDataEntry::try_from(&data[..]),
+            Err(BinpackError::InvalidFormat(_))
+        ));
+
+        let data = [0u8; 40];
+        assert!(PackedTrainingDataEntry::try_from(&data[..]).is_ok());
+    }
+
+    #[test]
+    fn test_flat_entry_round_trip() {
+        let entry = TrainingDataEntry {
+            pos: Position::from_fen(
+                "1r3rk1/p2qnpb1/6pp/P1p1p3/3nN3/2QP2P1/R3PPBP/2B2RK1 b - - 2 20",
+            )
+            .unwrap(),
+            mv: Move::new(
+                Square::new(61),
+                Square::new(58),
+                MoveType::Normal,
+                Piece::none(),
+            ),
+            score: -127,
+            ply: 39,
+            result: -1,
+        };
+
+        let flat = entry.as_flat();
+        assert_eq!(flat.score, -127);
+        assert_eq!(flat.ply, 39);
+        assert_eq!(flat.result, -1);
+        assert_eq!(flat.rule50, 2);
+        assert_eq!(TrainingDataEntry::from_flat(&flat), entry);
+
+        assert_eq!(std::mem::size_of::<FlatEntry>(), 34);
+    }
+
+    #[test]
+    fn test_raw_ply_result() {
+        let packed = PackedTrainingDataEntry::from_slice(&[
+            98, 121, 192, 21, 24, 76, 241, 100, 100, 106, 0, 4, 8, 48, 2, 17, 17, 145, 19, 117,
+            247, 0, 0, 0, 61, 232, 0, 253, 0, 39, 0, 2,
+        ]);
+        let mut entry = packed.unpack_entry_with(ScoreEncoding::default());
+        assert_eq!(entry.raw_ply_result(), 39);
+
+        for pr in [39, 39 | 1 << 14, 39 | 2 << 14, 39 | 3 << 14, 0x3FFF] {
+            entry.set_raw_ply_result(pr);
+            assert_eq!(entry.raw_ply_result(), pr);
+
+            let unpacked = PackedTrainingDataEntry::from_entry(&entry)
+                .unpack_entry_with(ScoreEncoding::default());
+            assert_eq!(unpacked.raw_ply_result(), pr);
+        }
+
+        entry.set_raw_ply_result(40 | 2 << 14);
+        assert_eq!((entry.ply, entry.result), (40, 1));
     }
 
     #[test]
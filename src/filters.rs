@@ -0,0 +1,103 @@
+use std::fs::File;
+use std::path::Path;
+
+use crate::chess::r#move::Move;
+use crate::{
+    CompressedTrainingDataEntryReader, CompressedTrainingDataEntryWriter, TrainingDataEntry,
+    TranscodeError,
+};
+
+/// Copy the entries of the binpack at `input` for which `predicate` returns true
+/// to `output`. Returns the number of entries written.
+pub fn filter_file<P, Q, F>(input: P, output: Q, mut predicate: F) -> Result<u64, TranscodeError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&TrainingDataEntry) -> bool,
+{
+    let mut reader = CompressedTrainingDataEntryReader::open(input)?;
+    let mut writer = CompressedTrainingDataEntryWriter::new(File::create(output)?)?;
+    let mut count = 0;
+
+    while reader.has_next() {
+        let entry = reader.try_next()?;
+
+        if predicate(&entry) {
+            writer.write_entry(&entry)?;
+            count += 1;
+        }
+    }
+
+    writer.flush_chunk()?;
+
+    Ok(count)
+}
+
+/// Keeps entries whose move is not a capture or a capture that doesn't lose
+/// material according to [`Position::see_ge`](crate::chess::position::Position::see_ge).
+/// Entries without a move, e.g. from `write_fen_score`, are kept.
+pub fn good_capture_only(entry: &TrainingDataEntry) -> bool {
+    entry.mv == Move::null()
+        || entry.pos.captured_piece(entry.mv).is_none()
+        || entry.pos.see_ge(entry.mv, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::{coords::Square, position::Position};
+
+    fn entry(fen: &str, mv: Move) -> TrainingDataEntry {
+        TrainingDataEntry {
+            pos: Position::from_fen(fen).unwrap(),
+            mv,
+            score: 0,
+            ply: 0,
+            result: 0,
+        }
+    }
+
+    #[test]
+    fn test_good_capture_only() {
+        // the queen takes a defended pawn
+        let losing = entry(
+            "4k3/8/4p3/3p4/8/8/3Q4/4K3 w - - 0 1",
+            Move::normal(Square::new(11), Square::new(35)),
+        );
+        // rook for rook
+        let equal = entry(
+            "3rk3/8/8/8/8/8/8/3RK3 w - - 0 1",
+            Move::normal(Square::D1, Square::D8),
+        );
+        let quiet = entry(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Move::normal(Square::G1, Square::new(21)),
+        );
+        let no_move = entry("4k3/8/8/8/8/8/8/4K3 w - - 0 1", Move::null());
+
+        assert!(!good_capture_only(&losing));
+        assert!(good_capture_only(&equal));
+        assert!(good_capture_only(&quiet));
+        assert!(good_capture_only(&no_move));
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.binpack");
+        let output = dir.path().join("out.binpack");
+
+        let mut writer =
+            CompressedTrainingDataEntryWriter::new(File::create(&input).unwrap()).unwrap();
+        for e in [&losing, &equal, &quiet, &no_move] {
+            writer.write_entry(e).unwrap();
+        }
+        writer.flush_chunk().unwrap();
+        drop(writer);
+
+        assert_eq!(filter_file(&input, &output, good_capture_only).unwrap(), 3);
+
+        let mut reader = CompressedTrainingDataEntryReader::open(&output).unwrap();
+        assert_eq!(reader.next(), equal);
+        assert_eq!(reader.next(), quiet);
+        assert_eq!(reader.next(), no_move);
+        assert!(!reader.has_next());
+    }
+}
@@ -1,22 +1,64 @@
+mod chains;
 mod common;
+#[cfg(feature = "ndjson")]
+mod ndjson;
 mod reader;
+mod split;
+mod stats;
+mod transcode;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 mod writer;
 
 pub mod chess;
+pub mod filters;
+
+pub use chains::normalize_chains;
 
 pub use common::binpack_error::BinpackError;
+pub use common::compressed_move::CompressedMove;
+pub use common::compressed_position::CompressedPosition;
+pub use common::entry::EntryError;
+pub use common::entry::FlatEntry;
+pub use common::entry::ScoreEncoding;
 pub use common::entry::TrainingDataEntry;
 
+#[cfg(feature = "ndjson")]
+pub use ndjson::from_ndjson;
+#[cfg(feature = "ndjson")]
+pub use ndjson::to_ndjson;
+#[cfg(feature = "ndjson")]
+pub use ndjson::NdjsonError;
+
 pub use reader::parse_chunk;
 pub use reader::read_chunk_into;
+pub use reader::read_positions;
+#[cfg(feature = "gzip")]
+pub use reader::BinpackInput;
 pub use reader::ChunkReader;
 pub use reader::CompressedReaderError;
 pub use reader::CompressedTrainingDataEntryReader;
+#[cfg(feature = "gzip")]
+pub use reader::GzipFile;
+pub use reader::PositionReader;
+pub use reader::ReaderErrorKind;
+pub use reader::ShuffledReader;
+
+pub use split::split_file;
+pub use split::SplitError;
+
+pub use stats::compute_stats;
+pub use stats::DatasetStats;
+
+pub use transcode::transcode;
+pub use transcode::TranscodeError;
+pub use transcode::TranscodeOptions;
 
 pub use writer::CompressedTrainingDataEntryWriter;
 pub use writer::CompressedWriterError;
+pub use writer::DedupWriter;
+pub use writer::ReorderingWriter;
+pub use writer::WriterErrorKind;
 
 #[cfg(target_arch = "wasm32")]
 pub use wasm::parse_binpack_chunk;
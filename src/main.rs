@@ -6,8 +6,6 @@ fn main() {
     let file =
         File::open("..\\..\\stockfish-data\\test80-2024-06-jun-2tb7p.min-v2.v6.binpack").unwrap();
 
-    let filesize = file.metadata().unwrap().len();
-
     let mut reader = CompressedTrainingDataEntryReader::new(file).unwrap();
 
     let mut num_entries: u64 = 0;
@@ -29,7 +27,7 @@ fn main() {
         num_entries += 1;
 
         if num_entries.is_multiple_of(1000000) {
-            let percentage = reader.read_bytes() as f64 / filesize as f64 * 100.0;
+            let percentage = reader.progress() * 100.0;
 
             print_update(num_entries, percentage, t0);
         }
@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_json::Number;
+use thiserror::Error;
+
+use crate::chess::{
+    position::{Position, PositionError},
+    r#move::Move,
+};
+use crate::{
+    CompressedReaderError, CompressedTrainingDataEntryReader, CompressedTrainingDataEntryWriter,
+    CompressedWriterError, TrainingDataEntry,
+};
+
+#[derive(Debug, Error)]
+pub enum NdjsonError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Reader error: {0}")]
+    Reader(#[from] CompressedReaderError),
+    #[error("Writer error: {0}")]
+    Writer(#[from] CompressedWriterError),
+    #[error("Invalid position: {0:?}")]
+    InvalidPosition(PositionError),
+    #[error("Line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+type Result<T> = std::result::Result<T, NdjsonError>;
+
+/// A single line of the NDJSON representation
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonEntry {
+    fen: String,
+    #[serde(rename = "move")]
+    mv: String,
+    #[serde(deserialize_with = "integer")]
+    score: i16,
+    #[serde(deserialize_with = "integer")]
+    ply: u16,
+    #[serde(deserialize_with = "integer")]
+    result: i16,
+}
+
+/// Convert a binpack to newline delimited JSON, one object per entry, e.g.
+/// `{"fen":"...","move":"e2e4","score":35,"ply":0,"result":0}`.
+/// A null move is written as `0000`. Returns the number of entries written.
+pub fn to_ndjson<P: AsRef<Path>, Q: AsRef<Path>>(input_binpack: P, output_json: Q) -> Result<u64> {
+    let mut reader = CompressedTrainingDataEntryReader::open(input_binpack)?;
+    let mut output = BufWriter::new(File::create(output_json)?);
+    let mut count = 0;
+
+    while reader.has_next() {
+        serde_json::to_writer(&mut output, &JsonEntry::from_entry(&reader.try_next()?)?)
+            .map_err(io::Error::from)?;
+        writeln!(output)?;
+        count += 1;
+    }
+
+    output.flush()?;
+
+    Ok(count)
+}
+
+/// Convert newline delimited JSON as written by [`to_ndjson`] back to a binpack.
+/// Empty lines and unknown fields are skipped, numbers may be written as floats
+/// without a fractional part, e.g. `35.0`. Returns the number of entries written.
+pub fn from_ndjson<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_json: P,
+    output_binpack: Q,
+) -> Result<u64> {
+    let input = BufReader::new(File::open(input_json)?);
+    let mut writer = CompressedTrainingDataEntryWriter::new(File::create(output_binpack)?)?;
+    let mut count = 0;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry = serde_json::from_str::<JsonEntry>(&line)
+            .map_err(|e| e.to_string())
+            .and_then(|json| json.into_entry())
+            .map_err(|message| NdjsonError::Parse {
+                line: index + 1,
+                message,
+            })?;
+
+        writer.write_entry(&entry)?;
+        count += 1;
+    }
+
+    writer.flush_chunk()?;
+
+    Ok(count)
+}
+
+impl JsonEntry {
+    fn from_entry(entry: &TrainingDataEntry) -> Result<Self> {
+        Ok(Self {
+            fen: entry.pos.fen().map_err(NdjsonError::InvalidPosition)?,
+            mv: entry.mv.as_uci(),
+            score: entry.score,
+            ply: entry.ply,
+            result: entry.result,
+        })
+    }
+
+    fn into_entry(self) -> std::result::Result<TrainingDataEntry, String> {
+        let pos =
+            Position::from_fen(&self.fen).map_err(|_| format!("Invalid FEN `{}`", self.fen))?;
+        let mv = Move::from_uci(&pos, &self.mv).ok_or(format!("Illegal move `{}`", self.mv))?;
+
+        Ok(TrainingDataEntry {
+            pos,
+            mv,
+            score: self.score,
+            ply: self.ply,
+            result: self.result,
+        })
+    }
+}
+
+// Deserialize an integer which may also be written as a float without a fractional part
+fn integer<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<i64>,
+{
+    let number = Number::deserialize(deserializer)?;
+
+    let value = number
+        .as_i64()
+        .or_else(|| {
+            number
+                .as_f64()
+                .filter(|f| f.fract() == 0.0 && f.abs() < i64::MAX as f64)
+                .map(|f| f as i64)
+        })
+        .ok_or_else(|| D::Error::custom(format!("{} is not an integer", number)))?;
+
+    T::try_from(value).map_err(|_| D::Error::custom(format!("{} is out of range", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ndjson_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("ep1.ndjson");
+        let binpack = dir.path().join("ep1.binpack");
+
+        assert_eq!(to_ndjson("./test/ep1.binpack", &json).unwrap(), 3);
+
+        let text = std::fs::read_to_string(&json).unwrap();
+        assert_eq!(
+            text.lines().next().unwrap(),
+            "{\"fen\":\"1q5b/1r5k/4p2p/1b2P1pN/3p4/6PP/1nP3B1/1Q2B1K1 w - - 0 35\",\
+             \"move\":\"c2c4\",\"score\":-201,\"ply\":68,\"result\":0}"
+        );
+
+        assert_eq!(from_ndjson(&json, &binpack).unwrap(), 3);
+
+        let mut original = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+        let mut converted = CompressedTrainingDataEntryReader::open(&binpack).unwrap();
+
+        while original.has_next() {
+            assert!(original.next().semantic_eq(&converted.next()));
+        }
+        assert!(!converted.has_next());
+    }
+
+    #[test]
+    fn test_from_ndjson_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("bad.ndjson");
+        let binpack = dir.path().join("bad.binpack");
+
+        std::fs::write(
+            &json,
+            "\n{\"fen\": \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\", \"move\": \"e1e3\", \"score\": 0, \"ply\": 0, \"result\": 0}\n",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            from_ndjson(&json, &binpack),
+            Err(NdjsonError::Parse { line: 2, .. })
+        ));
+
+        // not JSON, a fractional ply, a score out of range and a nested value instead of the FEN
+        for (bad, line) in [
+            ("[1, 2]", 1),
+            ("{\"fen\": \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\", \"move\": \"0000\", \"score\": 0, \"ply\": 0.5, \"result\": 0}", 1),
+            ("{\"fen\": \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\", \"move\": \"0000\", \"score\": 40000, \"ply\": 0, \"result\": 0}", 1),
+            ("\n\n{\"fen\": {\"board\": 1}, \"move\": \"0000\", \"score\": 0, \"ply\": 0, \"result\": 0}", 3),
+        ] {
+            std::fs::write(&json, bad).unwrap();
+
+            match from_ndjson(&json, &binpack) {
+                Err(NdjsonError::Parse { line: l, .. }) => assert_eq!(l, line),
+                other => panic!("expected a parse error for {}, got {:?}", bad, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_ndjson_python_style() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("python.ndjson");
+        let binpack = dir.path().join("python.binpack");
+
+        // escaped FEN characters, float numbers and an unknown nested field
+        std::fs::write(
+            &json,
+            "{\"fen\": \"\\u0034k3/8/8/8/8/8/8/4K3 w - - 0 1\", \"move\": \"e1e2\", \"score\": 35.0, \
+             \"ply\": 0, \"result\": -1.0, \"meta\": {\"source\": \"a\\tb\\n\"}}\n",
+        )
+        .unwrap();
+
+        assert_eq!(from_ndjson(&json, &binpack).unwrap(), 1);
+
+        let entry = CompressedTrainingDataEntryReader::open(&binpack)
+            .unwrap()
+            .next();
+        assert_eq!(entry.pos.fen().unwrap(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(entry.mv.as_uci(), "e1e2");
+        assert_eq!(entry.score, 35);
+        assert_eq!(entry.result, -1);
+    }
+}
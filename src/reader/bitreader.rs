@@ -12,9 +12,10 @@ impl BitReader {
         }
     }
 
-    pub fn extract_bits_le8(&mut self, movetext: &[u8], count: usize) -> u8 {
+    /// Read `count` bits, None if that would read past the end of the movetext
+    pub fn extract_bits_le8(&mut self, movetext: &[u8], count: usize) -> Option<u8> {
         if count == 0 {
-            return 0;
+            return Some(0);
         }
 
         if self.read_bits_left == 0 {
@@ -22,41 +23,75 @@ impl BitReader {
             self.read_bits_left = 8;
         }
 
-        let byte = movetext[self.read_offset] << (8 - self.read_bits_left);
+        let byte = movetext.get(self.read_offset)? << (8 - self.read_bits_left);
 
         let mut bits = byte >> (8 - count);
 
         if count > self.read_bits_left {
             let spill_count = count - self.read_bits_left;
 
-            bits |= movetext[self.read_offset + 1] >> (8 - spill_count);
+            bits |= movetext.get(self.read_offset + 1)? >> (8 - spill_count);
 
             self.read_bits_left += 8;
             self.read_offset += 1;
         }
 
         self.read_bits_left -= count;
-        bits
+        Some(bits)
     }
 
-    pub fn extract_vle16(&mut self, movetext: &[u8], block_size: usize) -> u16 {
+    /// Read a variable length encoded value, None if that would read past
+    /// the end of the movetext or the value doesn't fit into 16 bits
+    pub fn extract_vle16(&mut self, movetext: &[u8], block_size: usize) -> Option<u16> {
         let mask = (1 << block_size) - 1;
         let mut v = 0u16;
         let mut offset = 0;
 
         loop {
-            let block = self.extract_bits_le8(movetext, block_size + 1) as u16;
-            v |= (block & mask) << offset;
+            let block = self.extract_bits_le8(movetext, block_size + 1)? as u16;
+            v |= (block & mask).checked_shl(offset)?;
             if (block >> block_size) == 0 {
                 break;
             }
-            offset += block_size;
+            offset += block_size as u32;
         }
 
-        v
+        Some(v)
     }
 
     pub fn num_read_bytes(&self) -> usize {
         self.read_offset + (self.read_bits_left != 8) as usize
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_reads() {
+        let movetext = [0b1011_0011, 0b0100_0000];
+        let mut reader = BitReader::new();
+
+        assert_eq!(reader.extract_bits_le8(&movetext, 3), Some(0b101));
+        assert_eq!(reader.extract_bits_le8(&movetext, 0), Some(0));
+        // spills into the second byte
+        assert_eq!(reader.extract_bits_le8(&movetext, 7), Some(0b100_1101));
+        assert_eq!(reader.extract_bits_le8(&movetext, 6), Some(0));
+        assert_eq!(reader.num_read_bytes(), 2);
+
+        // exactly at the end, nothing more to read
+        assert_eq!(reader.extract_bits_le8(&movetext, 1), None);
+
+        let mut reader = BitReader::new();
+        assert_eq!(
+            reader.extract_bits_le8(&movetext[..1], 8),
+            Some(0b1011_0011)
+        );
+        assert_eq!(reader.extract_bits_le8(&movetext[..1], 1), None);
+
+        // a continuation bit pointing past the end
+        let mut reader = BitReader::new();
+        assert_eq!(reader.extract_vle16(&[0xFF], 4), None);
+    }
+}
@@ -1,13 +1,18 @@
+use std::collections::VecDeque;
+use std::fs::File;
 use std::io::{self};
 use std::io::{Read, Seek};
+use std::path::Path;
 use thiserror::Error;
 
 use crate::common::{
-    binpack_error::BinpackError, compressed_training_file_reader::CompressedTrainingDataFileReader,
-    entry::PackedTrainingDataEntry, entry::TrainingDataEntry,
+    binpack_error::BinpackError,
+    compressed_training_file_reader::{CompressedTrainingDataFileReader, HEADER_SIZE},
+    entry::{PackedTrainingDataEntry, ScoreEncoding, TrainingDataEntry},
 };
 
 use super::move_score_list_reader::PackedMoveScoreListReader;
+use super::shuffled_reader::ShuffledReader;
 
 const SUGGESTED_CHUNK_SIZE: usize = 8192;
 
@@ -23,6 +28,36 @@ pub enum CompressedReaderError {
     BinpackError(#[from] BinpackError),
 }
 
+/// The variant of a [`CompressedReaderError`] without its payload, for comparisons.
+/// IO errors are represented by their [`io::ErrorKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReaderErrorKind {
+    Io(io::ErrorKind),
+    InvalidFormat,
+    InvalidMagic,
+    EndOfFile,
+}
+
+impl CompressedReaderError {
+    /// Get the kind of the error, nested binpack errors are flattened
+    pub fn kind(&self) -> ReaderErrorKind {
+        match self {
+            CompressedReaderError::Io(e) => ReaderErrorKind::Io(e.kind()),
+            CompressedReaderError::InvalidFormat(_) => ReaderErrorKind::InvalidFormat,
+            CompressedReaderError::EndOfFile => ReaderErrorKind::EndOfFile,
+            CompressedReaderError::BinpackError(e) => match e {
+                BinpackError::Io(e) => ReaderErrorKind::Io(e.kind()),
+                BinpackError::BadMagic(_) | BinpackError::GzipCompressed => {
+                    ReaderErrorKind::InvalidMagic
+                }
+                BinpackError::ShortChunk { .. }
+                | BinpackError::BadChunkSize(_)
+                | BinpackError::InvalidFormat(_) => ReaderErrorKind::InvalidFormat,
+            },
+        }
+    }
+}
+
 type Result<T> = std::result::Result<T, CompressedReaderError>;
 
 /// Read the next raw binpack chunk payload into `buffer`.
@@ -65,6 +100,15 @@ pub struct CompressedTrainingDataEntryReader<T: Read + Seek> {
     chunk_reader: ChunkReader,
     input_file: Option<CompressedTrainingDataFileReader<T>>,
     is_end: bool,
+    source_name: String,
+    score_encoding: ScoreEncoding,
+    strict: bool,
+    num_chunks_loaded: u64,
+    stem_offset: u64,
+    pending_files: VecDeque<CompressedTrainingDataFileReader<T>>,
+    finished_bytes: u64,
+    entries_read: u64,
+    pending_error: Option<CompressedReaderError>,
 }
 
 #[derive(Debug, Default)]
@@ -72,6 +116,9 @@ pub struct ChunkReader {
     movelist_reader: Option<PackedMoveScoreListReader>,
     offset: usize,
     is_end: bool,
+    score_encoding: ScoreEncoding,
+    strict: bool,
+    rejected_stem: bool,
 }
 
 /*
@@ -121,13 +168,37 @@ impl<T: Read + Seek> CompressedTrainingDataEntryReader<T> {
     /// }
     /// ```
     pub fn new(file: T) -> Result<Self> {
-        let chunk = Vec::with_capacity(SUGGESTED_CHUNK_SIZE);
+        Self::from_files(vec![file], SUGGESTED_CHUNK_SIZE)
+    }
+
+    /// Read the files one after another as if they were a single file.
+    /// Chains never span files, so a file boundary acts like a chunk boundary.
+    fn from_files(files: Vec<T>, buffer_size: usize) -> Result<Self> {
+        let chunk = Vec::with_capacity(buffer_size);
+
+        let mut files = files
+            .into_iter()
+            .map(CompressedTrainingDataFileReader::new)
+            .collect::<io::Result<VecDeque<_>>>()?;
+
+        let Some(input_file) = files.pop_front() else {
+            return Err(CompressedReaderError::EndOfFile);
+        };
 
         let mut reader = Self {
             chunk,
             chunk_reader: ChunkReader::default(),
-            input_file: Some(CompressedTrainingDataFileReader::new(file)?),
+            input_file: Some(input_file),
             is_end: false,
+            source_name: String::new(),
+            score_encoding: ScoreEncoding::default(),
+            strict: false,
+            num_chunks_loaded: 0,
+            stem_offset: 0,
+            pending_files: files,
+            finished_bytes: 0,
+            entries_read: 0,
+            pending_error: None,
         };
 
         if !reader.load_next_chunk()? {
@@ -138,13 +209,119 @@ impl<T: Read + Seek> CompressedTrainingDataEntryReader<T> {
         Ok(reader)
     }
 
+    /// Set a label for the data source, e.g. for logging and error messages.
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        self.source_name = name.into();
+        self
+    }
+
+    /// Set the byte order of the stem scores, for binpacks not written by Stockfish.
+    /// The default is [`ScoreEncoding::BigEndianSigned`], matching Stockfish.
+    /// Must be set before the first entry is read.
+    pub fn with_score_encoding(mut self, score_encoding: ScoreEncoding) -> Self {
+        self.score_encoding = score_encoding;
+        self.chunk_reader.score_encoding = score_encoding;
+        self
+    }
+
+    /// Verify that the ply stored in every stem matches the ply of its position,
+    /// i.e. the side to move. Continuation entries take their ply and result
+    /// from the stem, so checking the stem covers the whole chain.
+    /// A violation is returned as an error from [`Self::try_next`],
+    /// the rest of the chunk is skipped and reading resumes with the next chunk.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self.chunk_reader.strict = strict;
+        self
+    }
+
+    /// Get the path this reader was opened with or the label set with `with_source_name`,
+    /// empty if neither was given.
+    pub fn source_name(&self) -> &str {
+        &self.source_name
+    }
+
     pub fn into_inner(&mut self) -> io::Result<T> {
         self.input_file.take().unwrap().into_inner()
     }
 
-    /// Get how much of the file has been read so far
+    /// Get how much of the file has been read so far,
+    /// summed over all files for a reader created with `new_multi`
     pub fn read_bytes(&self) -> u64 {
-        self.input_file.as_ref().unwrap().read_bytes()
+        self.finished_bytes + self.input_file.as_ref().unwrap().read_bytes()
+    }
+
+    /// Get the size of the file in bytes,
+    /// summed over all files for a reader created with `new_multi`
+    pub fn file_size(&self) -> u64 {
+        let pending: u64 = self.pending_files.iter().map(|f| f.file_size()).sum();
+
+        self.finished_bytes + self.input_file.as_ref().unwrap().file_size() + pending
+    }
+
+    /// Get the absolute file offset of the next stem to be read.
+    /// While [`Self::is_next_entry_continuation`] is true the offset points into
+    /// the movetext of the current chain instead, record it between chains only.
+    /// At the start of a chunk it can be passed to [`Self::new_at_byte`] to resume.
+    pub fn byte_position(&self) -> u64 {
+        let chunk_start = self.read_bytes() - (HEADER_SIZE + self.chunk.len()) as u64;
+        chunk_start + (HEADER_SIZE + self.chunk_reader.offset) as u64
+    }
+
+    /// Get the index of the chunk entries are currently read from, starting at 0
+    pub fn current_chunk_index(&self) -> u64 {
+        self.num_chunks_loaded.saturating_sub(1)
+    }
+
+    /// Get how many bytes of the current chunk have been decoded,
+    /// a chain only counts once all of its entries have been read
+    pub fn bytes_in_current_chunk(&self) -> usize {
+        self.chunk_reader.offset
+    }
+
+    /// Get the fraction of the file decoded so far, in the range 0.0..=1.0.
+    /// Returns 1.0 once all entries have been read or if the file is empty.
+    pub fn progress(&self) -> f64 {
+        let file_size = self.file_size();
+
+        if self.is_end || file_size == 0 {
+            return 1.0;
+        }
+
+        // the current chunk is loaded in full, only count the entries read from it
+        let unread = (HEADER_SIZE + self.chunk.len() - self.chunk_reader.offset) as u64;
+        let decoded = self.read_bytes().saturating_sub(unread);
+
+        (decoded as f64 / file_size as f64).min(1.0)
+    }
+
+    /// Estimate how many entries are left to read, extrapolating the entries per
+    /// byte decoded so far to the rest of the file. This is only an estimate,
+    /// the density varies between games and stems take more space than moves.
+    /// Returns 0 before the first entry was read and after the last one.
+    pub fn estimated_entries(&self) -> u64 {
+        if self.is_end || self.entries_read == 0 {
+            return 0;
+        }
+
+        let chunk_start = self.read_bytes() - (HEADER_SIZE + self.chunk.len()) as u64;
+        let movetext = self
+            .chunk_reader
+            .movelist_reader
+            .as_ref()
+            .map_or(0, |reader| reader.num_read_bytes());
+        let in_chunk = self.chunk_reader.offset + movetext;
+
+        // the header of a chunk counts once its first entry was read
+        let consumed = if in_chunk == 0 {
+            chunk_start
+        } else {
+            chunk_start + (HEADER_SIZE + in_chunk) as u64
+        };
+
+        let total = self.entries_read as f64 * self.file_size() as f64 / consumed as f64;
+
+        (total.round() as u64).saturating_sub(self.entries_read)
     }
 
     /// Read the next raw binpack chunk payload into `buffer`.
@@ -153,7 +330,7 @@ impl<T: Read + Seek> CompressedTrainingDataEntryReader<T> {
     /// reads the next chunk header and payload, resizes `buffer` to the chunk
     /// size, and overwrites it with the chunk bytes before returning `Ok(true)`.
     pub fn read_next_chunk_into(&mut self, buffer: &mut Vec<u8>) -> Result<bool> {
-        if !self.input_file.as_mut().unwrap().has_next_chunk() {
+        if !self.has_next_chunk() {
             return Ok(false);
         }
 
@@ -185,32 +362,117 @@ impl<T: Read + Seek> CompressedTrainingDataEntryReader<T> {
     }
 
     /// Get the next TrainingDataEntry
+    ///
+    /// Panics if the data is malformed, use [`Self::try_next`] to handle
+    /// corrupt files gracefully.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> TrainingDataEntry {
-        let entry = self.chunk_reader.next(&self.chunk);
+        self.try_next().unwrap()
+    }
+
+    /// Get the next TrainingDataEntry, returning an error if the data is malformed
+    /// or [`CompressedReaderError::EndOfFile`] once all entries have been read.
+    /// After an error the reader is exhausted and `has_next()` returns false,
+    /// except for a stem rejected in strict mode, see [`Self::with_strict`].
+    /// An error loading the following chunk is returned by the call after the
+    /// last entry of the current chunk, so that entry is not lost.
+    pub fn try_next(&mut self) -> Result<TrainingDataEntry> {
+        if self.is_end {
+            return Err(CompressedReaderError::EndOfFile);
+        }
 
-        if !self.chunk_reader.has_next(&self.chunk) {
-            self.fetch_next_chunk_if_needed();
+        if let Some(e) = self.pending_error.take() {
+            self.is_end = true;
+            return Err(e);
         }
 
+        let entry = self.chunk_reader.try_next(&self.chunk);
+
+        if entry.is_err() {
+            if !self.chunk_reader.rejected_stem {
+                self.is_end = true;
+            } else if let Err(e) = self.fetch_next_chunk_if_needed() {
+                self.pending_error = Some(e);
+            }
+
+            return entry;
+        }
+
+        if let Err(e) = self.fetch_next_chunk_if_needed() {
+            self.pending_error = Some(e);
+        }
+
+        self.entries_read += 1;
+
         entry
     }
 
+    /// Turn the reader into an iterator over its remaining entries in a reproducible
+    /// pseudo-random order. Up to `window` decoded entries are buffered and each
+    /// step yields a random one of them, refilling from the stream, so memory stays
+    /// bounded but entries only move within roughly `window` positions.
+    /// This breaks continuation chains, the entries should be treated as independent.
+    pub fn shuffled(self, window: usize, seed: u64) -> ShuffledReader<T> {
+        ShuffledReader::new(self, window, seed)
+    }
+
+    /// Skip up to `n` entries, returning how many were skipped, fewer at the end of the file.
+    /// Entries are decoded without being returned, the moves of a chain can't be skipped
+    /// blindly since the bit width of each encoded move depends on the position.
+    pub fn skip(&mut self, n: u64) -> Result<u64> {
+        let mut skipped = 0;
+
+        while skipped < n && self.has_next() {
+            self.try_next()?;
+            skipped += 1;
+        }
+
+        Ok(skipped)
+    }
+
+    /// Get the next TrainingDataEntry together with the byte offset of its stem
+    /// in the file. Entries continuing a chain report the offset of the stem
+    /// the chain starts with, so all entries of a chain share one offset.
+    pub fn next_with_offset(&mut self) -> Result<(u64, TrainingDataEntry)> {
+        if !self.is_next_entry_continuation() {
+            let chunk_start = self.read_bytes() - (HEADER_SIZE + self.chunk.len()) as u64;
+            self.stem_offset = chunk_start + (HEADER_SIZE + self.chunk_reader.offset) as u64;
+        }
+
+        let offset = self.stem_offset;
+
+        self.try_next().map(|entry| (offset, entry))
+    }
+
     // EBNF: BLOCK
-    fn fetch_next_chunk_if_needed(&mut self) {
+    fn fetch_next_chunk_if_needed(&mut self) -> Result<()> {
         if self.chunk_reader.has_next(&self.chunk) {
-            return;
+            return Ok(());
         }
 
-        if self.load_next_chunk().unwrap() {
-            return;
+        if !self.load_next_chunk()? {
+            self.is_end = true;
+        }
+
+        Ok(())
+    }
+
+    // Move on to the next file once the current one is exhausted
+    fn has_next_chunk(&mut self) -> bool {
+        while !self.input_file.as_mut().unwrap().has_next_chunk() {
+            let Some(next) = self.pending_files.pop_front() else {
+                return false;
+            };
+
+            let finished = self.input_file.replace(next).unwrap();
+            self.finished_bytes += finished.file_size();
         }
 
-        self.is_end = true;
+        true
     }
 
     fn load_next_chunk(&mut self) -> Result<bool> {
-        if !self.input_file.as_mut().unwrap().has_next_chunk() {
+        if !self.has_next_chunk() {
             return Ok(false);
         }
 
@@ -219,13 +481,28 @@ impl<T: Read + Seek> CompressedTrainingDataEntryReader<T> {
             .unwrap()
             .read_next_chunk_into(&mut self.chunk)?;
 
-        self.chunk_reader = ChunkReader::default();
+        self.chunk_reader = ChunkReader::new(self.score_encoding).with_strict(self.strict);
+        self.num_chunks_loaded += 1;
 
         Ok(true)
     }
 }
 
 impl ChunkReader {
+    /// Create a chunk reader decoding the stem scores with the given byte order.
+    pub fn new(score_encoding: ScoreEncoding) -> Self {
+        Self {
+            score_encoding,
+            ..Default::default()
+        }
+    }
+
+    /// Verify the ply of every stem, see [`CompressedTrainingDataEntryReader::with_strict`].
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     /// Check whether another entry can be read from this chunk.
     pub fn has_next(&self, chunk: &[u8]) -> bool {
         if self
@@ -240,10 +517,24 @@ impl ChunkReader {
     }
 
     /// Read the next entry from this chunk.
+    ///
+    /// Panics if the chunk is malformed, see [`ChunkReader::try_next`].
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self, chunk: &[u8]) -> TrainingDataEntry {
+        self.try_next(chunk).unwrap()
+    }
+
+    /// Read the next entry from this chunk, returning an error if the chunk is malformed.
+    pub fn try_next(&mut self, chunk: &[u8]) -> Result<TrainingDataEntry> {
         if let Some(ref mut reader) = self.movelist_reader {
-            let entry = reader.next_entry(&chunk[self.offset..]);
+            let entry = match reader.next_entry(&chunk[self.offset..]) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.is_end = true;
+                    self.movelist_reader = None;
+                    return Err(e);
+                }
+            };
 
             if !reader.has_next() {
                 self.offset += reader.num_read_bytes();
@@ -251,7 +542,7 @@ impl ChunkReader {
                 self.finish_if_at_end(chunk);
             }
 
-            return entry;
+            return Ok(entry);
         }
 
         // We don't have a movelist reader, so we first need to extract the "stem" information
@@ -259,17 +550,47 @@ impl ChunkReader {
         // EBNF: Stem
         let entry = self.read_entry(chunk);
 
+        if self.strict {
+            if let Err(e) = Self::check_stem(&entry) {
+                self.is_end = true;
+                self.rejected_stem = true;
+                return Err(e);
+            }
+        }
+
         // EBNF: Count
         let num_plies = self.read_plies(chunk);
 
         if num_plies > 0 {
             // EBNF: MoveText
+            let remaining = chunk.len() - self.offset;
+
+            if PackedMoveScoreListReader::min_movetext_bytes(num_plies) > remaining {
+                self.is_end = true;
+                return Err(CompressedReaderError::InvalidFormat(format!(
+                    "Movetext of {} plies does not fit into the remaining {} bytes of the chunk",
+                    num_plies, remaining
+                )));
+            }
+
             self.movelist_reader = Some(PackedMoveScoreListReader::new(entry, num_plies));
         } else {
             self.finish_if_at_end(chunk);
         }
 
-        entry
+        Ok(entry)
+    }
+
+    fn check_stem(entry: &TrainingDataEntry) -> Result<()> {
+        if entry.pos.ply() != entry.ply {
+            return Err(CompressedReaderError::InvalidFormat(format!(
+                "Stem has ply {} but its position has ply {}",
+                entry.ply,
+                entry.pos.ply()
+            )));
+        }
+
+        Ok(())
     }
 
     fn read_entry(&mut self, chunk: &[u8]) -> TrainingDataEntry {
@@ -281,7 +602,7 @@ impl ChunkReader {
 
         self.offset += size;
 
-        packed.unpack_entry()
+        packed.unpack_entry_with(self.score_encoding)
     }
 
     fn read_plies(&mut self, chunk: &[u8]) -> u16 {
@@ -297,6 +618,87 @@ impl ChunkReader {
     }
 }
 
+impl CompressedTrainingDataEntryReader<File> {
+    /// Open the binpack at the given path for reading.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+
+        Ok(Self::new(file)?.with_source_name(path.as_ref().display().to_string()))
+    }
+
+    /// Open the binpack at the given path with `size` bytes reserved for the chunk buffer.
+    /// Each chunk is read with a single call straight into this buffer, which grows to
+    /// the largest chunk of the file. The default of 8 KiB matches chunks written by
+    /// Stockfish, reserving the chunk size of the file up front avoids reallocating
+    /// while the first chunks are read for files with larger chunks.
+    pub fn with_buffer_size<P: AsRef<Path>>(path: P, size: usize) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+
+        Ok(Self::from_files(vec![file], size)?
+            .with_source_name(path.as_ref().display().to_string()))
+    }
+
+    /// Open the binpack at the given path and start reading at `offset`, which must
+    /// be the start of a chunk header or of the first stem of a chunk,
+    /// e.g. a [`Self::byte_position`] recorded right after a chunk was finished.
+    pub fn new_at_byte<P: AsRef<Path>>(path: P, offset: u64) -> Result<Self> {
+        let mut file = File::open(path.as_ref())?;
+        let file_size = file.seek(io::SeekFrom::End(0))?;
+
+        let mut chunk_start = 0;
+
+        while chunk_start != offset && chunk_start + HEADER_SIZE as u64 != offset {
+            if chunk_start > offset || chunk_start >= file_size {
+                return Err(CompressedReaderError::InvalidFormat(format!(
+                    "Offset {} is not at a chunk boundary, resume from an offset recorded at the start of a chunk",
+                    offset
+                )));
+            }
+
+            let mut header = [0u8; HEADER_SIZE];
+            file.seek(io::SeekFrom::Start(chunk_start))?;
+            file.read_exact(&mut header)?;
+
+            let chunk_size = u32::from_le_bytes(header[4..8].try_into().unwrap());
+            chunk_start += (HEADER_SIZE as u64) + chunk_size as u64;
+        }
+
+        file.seek(io::SeekFrom::Start(chunk_start))?;
+
+        Ok(Self::new(file)?.with_source_name(path.as_ref().display().to_string()))
+    }
+
+    /// Open several binpacks and read them as one stream, in the given order.
+    /// All files are opened up front, `file_size()` and `read_bytes()` report
+    /// the totals over all of them.
+    pub fn new_multi<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let files = paths
+            .iter()
+            .map(File::open)
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let source_name = paths
+            .iter()
+            .map(|p| p.as_ref().display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Ok(Self::from_files(files, SUGGESTED_CHUNK_SIZE)?.with_source_name(source_name))
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl CompressedTrainingDataEntryReader<super::gzip_input::BinpackInput> {
+    /// Open the binpack at the given path, plain or gzip compressed. Gzip input is
+    /// detected by a `.gz` extension or the magic bytes `1f 8b` and decompressed
+    /// while reading, see [`super::gzip_input::GzipFile`] for the costs of seeking.
+    pub fn open_auto<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = super::gzip_input::BinpackInput::open(path.as_ref())?;
+
+        Ok(Self::new(file)?.with_source_name(path.as_ref().display().to_string()))
+    }
+}
+
 impl CompressedTrainingDataEntryReader<io::Cursor<Vec<u8>>> {
     /// Create a reader from an owned byte buffer.
     ///
@@ -453,6 +855,162 @@ mod tests {
         }
 
         assert_eq!(num_entries, 3);
+
+        // reading past the end is an error, not a panic
+        assert_eq!(
+            reader.try_next().unwrap_err().kind(),
+            ReaderErrorKind::EndOfFile
+        );
+    }
+
+    #[test]
+    fn test_reader_source_name() {
+        let reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+        assert_eq!(reader.source_name(), "./test/ep1.binpack");
+
+        let file = std::fs::read("./test/ep1.binpack").unwrap();
+        let reader = CompressedTrainingDataEntryReader::from_bytes(file)
+            .unwrap()
+            .with_source_name("in-memory");
+        assert_eq!(reader.source_name(), "in-memory");
+    }
+
+    #[test]
+    fn test_reader_score_encoding() {
+        let big_endian = std::fs::read("./test/ep1.binpack").unwrap();
+
+        // the stem score follows the chunk header, the position and the move
+        let score_offset = 8 + 24 + 2;
+        let mut little_endian = big_endian.clone();
+        little_endian.swap(score_offset, score_offset + 1);
+
+        let read_all = |bytes: Vec<u8>, score_encoding| {
+            let mut reader = CompressedTrainingDataEntryReader::from_bytes(bytes)
+                .unwrap()
+                .with_score_encoding(score_encoding);
+            let mut entries = Vec::new();
+            while reader.has_next() {
+                entries.push(reader.next());
+            }
+            entries
+        };
+
+        let expected = read_all(big_endian, ScoreEncoding::BigEndianSigned);
+        assert_eq!(expected[0].score, -201);
+        assert_eq!(
+            read_all(little_endian.clone(), ScoreEncoding::LittleEndianSigned),
+            expected
+        );
+        assert_ne!(
+            read_all(little_endian, ScoreEncoding::BigEndianSigned),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_reader_chunk_position() {
+        let chunk = std::fs::read("./test/ep1.binpack").unwrap();
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(chunk.repeat(3)).unwrap();
+
+        assert_eq!(reader.current_chunk_index(), 0);
+        assert_eq!(reader.bytes_in_current_chunk(), 0);
+
+        // the stem and the ply count
+        reader.next();
+        assert_eq!(reader.bytes_in_current_chunk(), 34);
+
+        let mut chunk_indices = Vec::new();
+        while reader.has_next() {
+            chunk_indices.push(reader.current_chunk_index());
+            reader.next();
+        }
+
+        assert_eq!(chunk_indices, [0, 0, 1, 1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_reader_strict() {
+        let valid = std::fs::read("./test/ep1.binpack").unwrap();
+
+        let read_all = |bytes: Vec<u8>, strict| {
+            let mut reader = CompressedTrainingDataEntryReader::from_bytes(bytes)
+                .unwrap()
+                .with_strict(strict);
+            let mut entries = Vec::new();
+            while reader.has_next() {
+                entries.push(reader.try_next());
+            }
+            entries
+        };
+
+        assert!(read_all(valid.clone(), true).iter().all(|e| e.is_ok()));
+
+        // the stem ply 68 becomes 67, which doesn't match White to move
+        let ply_offset = 8 + 24 + 2 + 2;
+        let valid_chunk = valid.clone();
+        let mut corrupt = valid;
+        corrupt[ply_offset + 1] -= 1;
+
+        let tolerant = read_all(corrupt.clone(), false);
+        assert_eq!(tolerant.len(), 3);
+        assert!(tolerant.iter().all(|e| e.is_ok()));
+
+        // the stem itself is rejected and the rest of the chunk skipped
+        let strict = read_all(corrupt.clone(), true);
+        assert_eq!(strict.len(), 1);
+        assert!(matches!(
+            strict[0],
+            Err(CompressedReaderError::InvalidFormat(_))
+        ));
+
+        // reading resumes with the next chunk
+        let strict = read_all([corrupt, valid_chunk].concat(), true);
+        assert_eq!(strict.len(), 4);
+        assert!(strict[0].is_err());
+        assert!(strict[1..].iter().all(|e| e.is_ok()));
+    }
+
+    #[test]
+    fn test_reader_is_send() {
+        fn assert_send<T: Send>() {}
+
+        assert_send::<CompressedTrainingDataEntryReader<File>>();
+        assert_send::<CompressedTrainingDataEntryReader<Cursor<Vec<u8>>>>();
+        assert_send::<ChunkReader>();
+
+        // read on another thread
+        let reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+        let count = std::thread::spawn(move || {
+            let mut reader = reader;
+            let mut count = 0;
+            while reader.has_next() {
+                reader.next();
+                count += 1;
+            }
+            count
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_reader_progress() {
+        let mut reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+
+        assert_eq!(
+            reader.file_size(),
+            std::fs::metadata("./test/ep1.binpack").unwrap().len()
+        );
+        assert_eq!(reader.progress(), 0.0);
+
+        while reader.has_next() {
+            reader.next();
+            assert!((0.0..=1.0).contains(&reader.progress()));
+        }
+
+        assert_eq!(reader.progress(), 1.0);
     }
 
     #[test]
@@ -488,11 +1046,9 @@ mod tests {
 
     // test case for https://github.com/Disservin/binpack-rust/issues/17
     #[test]
-    #[should_panic(expected = "index out of bounds: the len is 0 but the index is 0")]
     fn test_reader_no_moves() {
-        // Safe API UB: CompressedTrainingDataEntryReader constructs a BitReader
-        // from a raw pointer without tracking length. A crafted chunk with
-        // num_plies > 0 but no movetext bytes triggers OOB reads.
+        // A crafted chunk with num_plies > 0 but no movetext bytes used to
+        // trigger out-of-bounds reads in the BitReader.
 
         // Valid packed entry bytes from crate tests (32 bytes).
         let entry_bytes: [u8; 32] = [
@@ -514,9 +1070,298 @@ mod tests {
         let cursor = Cursor::new(file);
         let mut reader = CompressedTrainingDataEntryReader::new(cursor).unwrap();
 
-        // First next() returns the stem entry and sets movelist_reader.
-        let _ = reader.next();
-        // Second next() consumes movetext via BitReader and triggers OOB.
-        let _ = reader.next();
+        // The stem claims a continuation that can't fit into the chunk.
+        assert!(matches!(
+            reader.try_next(),
+            Err(CompressedReaderError::InvalidFormat(_))
+        ));
+        assert!(!reader.has_next());
+    }
+
+    #[test]
+    fn test_reader_inflated_ply_count() {
+        let mut file = std::fs::read("./test/ep1.binpack").unwrap();
+
+        // The ply count directly follows the 8 byte header and the 32 byte stem.
+        let count_offset = 8 + PackedTrainingDataEntry::byte_size();
+        file[count_offset..count_offset + 2].copy_from_slice(&u16::MAX.to_be_bytes());
+
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(file).unwrap();
+
+        assert!(matches!(
+            reader.try_next(),
+            Err(CompressedReaderError::InvalidFormat(_))
+        ));
+        assert!(!reader.has_next());
+    }
+
+    #[test]
+    fn test_reader_new_multi() {
+        let paths = ["./test/ep1.binpack", "./test/ep1.binpack"];
+        let mut reader = CompressedTrainingDataEntryReader::new_multi(&paths).unwrap();
+
+        let size = std::fs::metadata("./test/ep1.binpack").unwrap().len();
+        assert_eq!(reader.file_size(), 2 * size);
+
+        let mut entries = Vec::new();
+        while reader.has_next() {
+            entries.push(reader.next());
+        }
+
+        assert_eq!(reader.read_bytes(), 2 * size);
+        assert_eq!(
+            entries.iter().map(|e| (e.ply, e.score)).collect::<Vec<_>>(),
+            [(68, -201), (69, 254), (70, -220)].repeat(2)
+        );
+
+        // empty files are passed over
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.binpack");
+        std::fs::write(&empty, []).unwrap();
+
+        let paths = [empty.as_path(), Path::new("./test/ep1.binpack")];
+        let mut reader = CompressedTrainingDataEntryReader::new_multi(&paths).unwrap();
+        assert_eq!(reader.skip(10).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_reader_new_at_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunks.binpack");
+        let chunk = std::fs::read("./test/ep1.binpack").unwrap();
+        std::fs::write(&path, chunk.repeat(3)).unwrap();
+
+        let mut reader = CompressedTrainingDataEntryReader::open(&path).unwrap();
+        assert_eq!(reader.byte_position(), HEADER_SIZE as u64);
+
+        // finish the first chunk
+        reader.skip(3).unwrap();
+        let checkpoint = reader.byte_position();
+        assert_eq!(checkpoint, (chunk.len() + HEADER_SIZE) as u64);
+
+        let mut remaining = Vec::new();
+        while reader.has_next() {
+            remaining.push(reader.next());
+        }
+        assert_eq!(reader.byte_position(), 3 * chunk.len() as u64);
+
+        for offset in [checkpoint, chunk.len() as u64] {
+            let mut resumed =
+                CompressedTrainingDataEntryReader::new_at_byte(&path, offset).unwrap();
+            assert_eq!(resumed.byte_position(), checkpoint);
+
+            let mut entries = Vec::new();
+            while resumed.has_next() {
+                entries.push(resumed.next());
+            }
+            assert_eq!(entries, remaining);
+        }
+
+        assert!(matches!(
+            CompressedTrainingDataEntryReader::new_at_byte(&path, checkpoint + 1),
+            Err(CompressedReaderError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_reader_with_buffer_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunks.binpack");
+        let chunk = std::fs::read("./test/ep1.binpack").unwrap();
+        std::fs::write(&path, chunk.repeat(100)).unwrap();
+
+        let read_all = |mut reader: CompressedTrainingDataEntryReader<File>| {
+            let mut entries = Vec::new();
+            while reader.has_next() {
+                entries.push(reader.next());
+            }
+            entries
+        };
+
+        let expected = read_all(CompressedTrainingDataEntryReader::open(&path).unwrap());
+        assert_eq!(expected.len(), 300);
+
+        for size in [0, 16, 1 << 20] {
+            let reader = CompressedTrainingDataEntryReader::with_buffer_size(&path, size).unwrap();
+            assert_eq!(read_all(reader), expected);
+        }
+    }
+
+    #[test]
+    fn test_reader_estimated_entries() {
+        let mut reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+        assert_eq!(reader.estimated_entries(), 0);
+        reader.skip(3).unwrap();
+        assert_eq!(reader.estimated_entries(), 0);
+
+        let chunk = std::fs::read("./test/ep1.binpack").unwrap();
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(chunk.repeat(100)).unwrap();
+
+        reader.skip(3).unwrap();
+        assert_eq!(reader.estimated_entries(), 297);
+
+        // within a chain the stem weighs more than the moves
+        reader.skip(148).unwrap();
+        let estimate = reader.estimated_entries();
+        assert!(estimate.abs_diff(149) <= 15, "{}", estimate);
+    }
+
+    #[test]
+    fn test_reader_truncated_movetext() {
+        let mut file = std::fs::read("./test/ep1.binpack").unwrap();
+
+        // Drop the last movetext byte and shrink the chunk to match.
+        file.pop();
+        let chunk_size = (file.len() - 8) as u32;
+        file[4..8].copy_from_slice(&chunk_size.to_le_bytes());
+
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(file).unwrap();
+
+        assert_eq!(reader.try_next().unwrap().ply, 68);
+        assert_eq!(reader.try_next().unwrap().ply, 69);
+        assert!(matches!(
+            reader.try_next(),
+            Err(CompressedReaderError::InvalidFormat(_))
+        ));
+        assert!(!reader.has_next());
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let mut file = std::fs::read("./test/ep1.binpack").unwrap();
+        file.truncate(file.len() - 5);
+
+        let err = CompressedTrainingDataEntryReader::from_bytes(file).unwrap_err();
+        assert_eq!(err.kind(), ReaderErrorKind::InvalidFormat);
+
+        let err =
+            CompressedTrainingDataEntryReader::from_bytes(b"BINX\0\0\0\0".to_vec()).unwrap_err();
+        assert_eq!(err.kind(), ReaderErrorKind::InvalidMagic);
+
+        let err =
+            CompressedTrainingDataEntryReader::open("./test/does-not-exist.binpack").unwrap_err();
+        assert_eq!(err.kind(), ReaderErrorKind::Io(io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_binpack_error_source() {
+        use std::error::Error;
+
+        let binpack_error = |bytes: Vec<u8>| {
+            let err = CompressedTrainingDataEntryReader::from_bytes(bytes).unwrap_err();
+            let source = err.source().expect("binpack errors are chained");
+            match source.downcast_ref::<BinpackError>().unwrap() {
+                BinpackError::BadMagic(magic) => format!("magic {:?}", magic),
+                BinpackError::ShortChunk { expected, actual } => {
+                    format!("short {} {}", expected, actual)
+                }
+                BinpackError::BadChunkSize(size) => format!("size {}", size),
+                other => panic!("unexpected error {}", other),
+            }
+        };
+
+        assert_eq!(
+            binpack_error(b"BINX\0\0\0\0".to_vec()),
+            "magic [66, 73, 78, 88]"
+        );
+
+        let mut file = std::fs::read("./test/ep1.binpack").unwrap();
+        file.truncate(file.len() - 5);
+        assert_eq!(binpack_error(file), "short 38 33");
+
+        assert_eq!(binpack_error(b"BIN".to_vec()), "short 8 3");
+
+        let mut header = b"BINP".to_vec();
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(binpack_error(header), format!("size {}", u32::MAX));
+    }
+
+    #[test]
+    fn test_gzip_detection() {
+        // gzip header, deflate without flags and an empty body
+        let gzip = vec![0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff];
+
+        let err = CompressedTrainingDataEntryReader::from_bytes(gzip).unwrap_err();
+        assert!(matches!(
+            err,
+            CompressedReaderError::BinpackError(BinpackError::GzipCompressed)
+        ));
+        assert!(err.to_string().contains("gzip"));
+    }
+
+    #[test]
+    fn test_gzip_magic_only_at_start() {
+        // a corrupt second header which happens to start like gzip
+        let mut bytes = std::fs::read("./test/ep1.binpack").unwrap();
+        bytes.extend([0x1f, 0x8b, 0, 0, 0, 0, 0, 0]);
+
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(bytes).unwrap();
+
+        // all entries of the valid chunk are returned before the error
+        for _ in 0..3 {
+            assert!(reader.try_next().is_ok());
+        }
+
+        assert!(reader.has_next());
+        let err = reader.try_next().unwrap_err();
+        assert!(!reader.has_next());
+
+        assert!(matches!(
+            err,
+            CompressedReaderError::BinpackError(BinpackError::BadMagic(_))
+        ));
+    }
+
+    #[test]
+    fn test_next_with_offset() {
+        let mut reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+
+        let mut offsets = Vec::new();
+        while reader.has_next() {
+            offsets.push(reader.next_with_offset().unwrap().0);
+        }
+
+        // a single chain right after the chunk header
+        assert_eq!(offsets, [8, 8, 8]);
+
+        let chunk = std::fs::read("./test/ep1.binpack").unwrap();
+        let bytes = chunk.repeat(3);
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(bytes.clone()).unwrap();
+
+        let mut offsets = Vec::new();
+        while reader.has_next() {
+            let (offset, entry) = reader.next_with_offset().unwrap();
+            offsets.push(offset);
+
+            // the stem of the chain can be decoded at the offset
+            let stem = PackedTrainingDataEntry::from_slice(&bytes[offset as usize..]);
+            assert_eq!(stem.unpack_entry_with(ScoreEncoding::default()).ply, 68);
+            assert!(entry.ply >= 68);
+        }
+
+        let size = chunk.len() as u64;
+        assert_eq!(offsets[..3], [8, 8, 8]);
+        assert_eq!(offsets[3..6], [size + 8; 3]);
+        assert_eq!(offsets[6..], [2 * size + 8; 3]);
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut reader = CompressedTrainingDataEntryReader::open("./test/ep1.binpack").unwrap();
+
+        assert_eq!(reader.skip(2).unwrap(), 2);
+        let third = reader.next();
+        assert_eq!(third.ply, 70);
+        assert_eq!(third.score, -220);
+        assert!(!reader.has_next());
+        assert_eq!(reader.skip(5).unwrap(), 0);
+
+        // across chunk boundaries
+        let chunk = std::fs::read("./test/ep1.binpack").unwrap();
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(chunk.repeat(3)).unwrap();
+        assert_eq!(reader.skip(4).unwrap(), 4);
+        assert_eq!(reader.next().ply, 69);
+        assert_eq!(reader.skip(10).unwrap(), 4);
+        assert!(!reader.has_next());
     }
 }
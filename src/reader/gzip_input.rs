@@ -0,0 +1,237 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A gzip compressed file which can be read and seeked like the decompressed file.
+/// Seeking only moves a logical position, the stream is decoded forward when read
+/// and restarted from the beginning for a backward seek.
+/// The decompressed size is determined by decoding the whole file once when it is
+/// first needed, which the binpack reader does when it is created.
+#[derive(Debug)]
+pub struct GzipFile {
+    // only None while the decoder is restarted
+    decoder: Option<MultiGzDecoder<BufReader<File>>>,
+    // bytes produced by the decoder so far
+    decoded: u64,
+    // position seen by the caller
+    pos: u64,
+    len: Option<u64>,
+}
+
+impl GzipFile {
+    pub fn new(file: File) -> Self {
+        Self {
+            decoder: Some(MultiGzDecoder::new(BufReader::new(file))),
+            decoded: 0,
+            pos: 0,
+            len: None,
+        }
+    }
+
+    fn decoder(&mut self) -> &mut MultiGzDecoder<BufReader<File>> {
+        self.decoder.as_mut().unwrap()
+    }
+
+    fn rewind_decoder(&mut self) -> io::Result<()> {
+        let mut file = self.decoder.take().unwrap().into_inner();
+        let rewound = file.seek(SeekFrom::Start(0));
+
+        self.decoder = Some(MultiGzDecoder::new(file));
+        self.decoded = 0;
+
+        rewound.map(|_| ())
+    }
+
+    // Move the decoder to the logical position, or to the end if it lies beyond
+    fn sync(&mut self) -> io::Result<()> {
+        if self.pos < self.decoded {
+            self.rewind_decoder()?;
+        }
+
+        if self.pos > self.decoded {
+            let ahead = self.pos - self.decoded;
+            let skipped = io::copy(&mut self.decoder().take(ahead), &mut io::sink())?;
+            self.decoded += skipped;
+        }
+
+        Ok(())
+    }
+
+    fn len(&mut self) -> io::Result<u64> {
+        if let Some(len) = self.len {
+            return Ok(len);
+        }
+
+        let rest = io::copy(self.decoder(), &mut io::sink())?;
+        self.decoded += rest;
+        self.len = Some(self.decoded);
+
+        Ok(self.decoded)
+    }
+}
+
+impl Read for GzipFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.sync()?;
+
+        if self.pos > self.decoded {
+            return Ok(0);
+        }
+
+        let n = self.decoder().read(buf)?;
+        self.decoded += n as u64;
+        self.pos += n as u64;
+
+        Ok(n)
+    }
+}
+
+impl Seek for GzipFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            SeekFrom::End(d) => self.len()?.checked_add_signed(d),
+        };
+
+        self.pos = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )
+        })?;
+
+        Ok(self.pos)
+    }
+}
+
+/// A binpack file which is either plain or gzip compressed,
+/// see [`CompressedTrainingDataEntryReader::open_auto`](crate::CompressedTrainingDataEntryReader::open_auto).
+#[derive(Debug)]
+pub enum BinpackInput {
+    Plain(File),
+    Gzip(Box<GzipFile>),
+}
+
+impl BinpackInput {
+    /// Open the file at `path`, decompressing it if it has a `.gz` extension
+    /// or starts with the gzip magic bytes `1f 8b`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut file = File::open(path)?;
+
+        let mut magic = [0u8; 2];
+        let is_gzip_magic = file.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC;
+        file.seek(SeekFrom::Start(0))?;
+
+        if is_gzip_magic || path.extension().is_some_and(|ext| ext == "gz") {
+            Ok(Self::Gzip(Box::new(GzipFile::new(file))))
+        } else {
+            Ok(Self::Plain(file))
+        }
+    }
+}
+
+impl Read for BinpackInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.read(buf),
+            Self::Gzip(file) => file.read(buf),
+        }
+    }
+}
+
+impl Seek for BinpackInput {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Plain(file) => file.seek(pos),
+            Self::Gzip(file) => file.seek(pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+    use crate::{CompressedTrainingDataEntryReader, TrainingDataEntry};
+
+    fn read_all<T: Read + Seek>(
+        mut reader: CompressedTrainingDataEntryReader<T>,
+    ) -> Vec<TrainingDataEntry> {
+        let mut entries = Vec::new();
+        while reader.has_next() {
+            entries.push(reader.next());
+        }
+        entries
+    }
+
+    #[test]
+    fn test_open_auto_gzip() {
+        let plain = std::fs::read("./test/ep1.binpack").unwrap().repeat(3);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&plain).unwrap();
+        let gzip = encoder.finish().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let gz_path = dir.path().join("ep1.binpack.gz");
+        // detected by the magic bytes alone
+        let no_ext_path = dir.path().join("ep1.binpack");
+        let plain_path = dir.path().join("plain.binpack");
+        std::fs::write(&gz_path, &gzip).unwrap();
+        std::fs::write(&no_ext_path, &gzip).unwrap();
+        std::fs::write(&plain_path, &plain).unwrap();
+
+        let expected = read_all(CompressedTrainingDataEntryReader::open(&plain_path).unwrap());
+        assert_eq!(expected.len(), 9);
+
+        for path in [&gz_path, &no_ext_path, &plain_path] {
+            let reader = CompressedTrainingDataEntryReader::open_auto(path).unwrap();
+            assert_eq!(reader.file_size(), plain.len() as u64);
+            assert_eq!(read_all(reader), expected);
+        }
+
+        // the plain reader reports the compression
+        assert!(CompressedTrainingDataEntryReader::open(&gz_path)
+            .unwrap_err()
+            .to_string()
+            .contains("gzip"));
+    }
+
+    #[test]
+    fn test_gzip_file_seek() {
+        let data = (0..=255u8).collect::<Vec<_>>();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(&encoder.finish().unwrap()).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let mut gzip = GzipFile::new(file);
+        let mut buf = [0u8; 4];
+
+        assert_eq!(gzip.seek(SeekFrom::End(0)).unwrap(), 256);
+        assert_eq!(gzip.seek(SeekFrom::Start(100)).unwrap(), 100);
+        gzip.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [100, 101, 102, 103]);
+
+        // backwards restarts the decoder
+        gzip.seek(SeekFrom::Current(-54)).unwrap();
+        gzip.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [50, 51, 52, 53]);
+
+        gzip.seek(SeekFrom::End(-2)).unwrap();
+        assert_eq!(gzip.read(&mut buf).unwrap(), 2);
+        assert_eq!(gzip.read(&mut buf).unwrap(), 0);
+        assert!(gzip.seek(SeekFrom::Current(-300)).is_err());
+    }
+}
@@ -1,9 +1,21 @@
 mod bitreader;
 mod compressed_reader;
+#[cfg(feature = "gzip")]
+mod gzip_input;
 mod move_score_list_reader;
+mod position_reader;
+mod shuffled_reader;
 
 pub use compressed_reader::parse_chunk;
 pub use compressed_reader::read_chunk_into;
 pub use compressed_reader::ChunkReader;
 pub use compressed_reader::CompressedReaderError;
 pub use compressed_reader::CompressedTrainingDataEntryReader;
+pub use compressed_reader::ReaderErrorKind;
+#[cfg(feature = "gzip")]
+pub use gzip_input::BinpackInput;
+#[cfg(feature = "gzip")]
+pub use gzip_input::GzipFile;
+pub use position_reader::read_positions;
+pub use position_reader::PositionReader;
+pub use shuffled_reader::ShuffledReader;
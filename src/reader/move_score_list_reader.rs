@@ -15,6 +15,11 @@ use crate::{
 };
 
 use super::bitreader::BitReader;
+use super::compressed_reader::CompressedReaderError;
+
+type Result<T> = std::result::Result<T, CompressedReaderError>;
+
+const SCORE_VLE_BLOCK_SIZE: usize = 4;
 
 #[derive(Debug)]
 pub struct PackedMoveScoreListReader {
@@ -36,23 +41,30 @@ impl PackedMoveScoreListReader {
         }
     }
 
+    /// Lower bound of the movetext size for the given number of plies.
+    /// Every ply stores at least one score block, piece and move ids may use zero bits.
+    pub fn min_movetext_bytes(num_plies: u16) -> usize {
+        (num_plies as usize * (SCORE_VLE_BLOCK_SIZE + 1)).div_ceil(8)
+    }
+
     pub fn has_next(&self) -> bool {
         self.num_read_plies < self.num_plies
     }
 
-    // Get the next TrainingDataEntry from the movetext
-    pub fn next_entry(&mut self, movetext: &[u8]) -> TrainingDataEntry {
+    // Get the next TrainingDataEntry from the movetext,
+    // an error if the movetext ends early or encodes a move that doesn't exist
+    pub fn next_entry(&mut self, movetext: &[u8]) -> Result<TrainingDataEntry> {
         self.entry.pos.do_move(self.entry.mv);
-        let (mv, score) = self.next_move_score(movetext);
+        let (mv, score) = self.next_move_score(movetext)?;
         self.entry.mv = mv;
         self.entry.score = score;
         self.entry.ply += 1;
         self.entry.result = -self.entry.result;
-        self.entry
+        Ok(self.entry)
     }
 
     // Read a move and score from the movetext
-    pub fn next_move_score(&mut self, movetext: &[u8]) -> (Move, i16) {
+    pub fn next_move_score(&mut self, movetext: &[u8]) -> Result<(Move, i16)> {
         // if !self.has_next() {
         //     return Ok(None);
         // }
@@ -64,47 +76,75 @@ impl PackedMoveScoreListReader {
         let their_pieces = pos.pieces_bb(!side_to_move);
         let occupied = our_pieces | their_pieces;
 
-        let piece_id = self
-            .reader
-            .extract_bits_le8(movetext, used_bits_safe(our_pieces.count() as u64));
+        let piece_id = self.extract_bits(movetext, our_pieces.count() as u64)?;
 
         // Extract the move
-        let move_ = self.decode_move(movetext, piece_id, occupied);
+        let move_ = self.decode_move(movetext, piece_id, occupied)?;
 
         // Extract the score
-        let score = self.decode_score(movetext);
+        let score = self.decode_score(movetext)?;
 
         self.last_score = -score;
 
         self.num_read_plies += 1;
 
-        (move_, score)
+        Ok((move_, score))
     }
 
     // EBNF: EncodedMove
-    fn decode_score(&mut self, movetext: &[u8]) -> i16 {
-        const SCORE_VLE_BLOCK_SIZE: usize = 4;
-        let delta = unsigned_to_signed(self.reader.extract_vle16(movetext, SCORE_VLE_BLOCK_SIZE));
+    fn decode_score(&mut self, movetext: &[u8]) -> Result<i16> {
+        let delta = self
+            .reader
+            .extract_vle16(movetext, SCORE_VLE_BLOCK_SIZE)
+            .ok_or_else(|| self.out_of_bounds(movetext))?;
+
+        Ok(self.last_score.wrapping_add(unsigned_to_signed(delta)))
+    }
 
-        self.last_score.wrapping_add(delta)
+    // Read an index into `count` choices
+    fn extract_bits(&mut self, movetext: &[u8], count: u64) -> Result<u8> {
+        self.reader
+            .extract_bits_le8(movetext, used_bits_safe(count))
+            .ok_or_else(|| self.out_of_bounds(movetext))
+    }
+
+    fn out_of_bounds(&self, movetext: &[u8]) -> CompressedReaderError {
+        CompressedReaderError::InvalidFormat(format!(
+            "Movetext of {} plies ends after {} bytes at ply {}",
+            self.num_plies,
+            movetext.len(),
+            self.num_read_plies + 1
+        ))
+    }
+
+    // The square of the n-th set bit, an error if there are fewer set bits
+    fn nth_square(bb: Bitboard, n: u64) -> Result<Square> {
+        if n >= bb.count() as u64 {
+            return Err(CompressedReaderError::InvalidFormat(format!(
+                "Move index {} out of range of {} choices",
+                n,
+                bb.count()
+            )));
+        }
+
+        Ok(Square::new(nth_set_bit_index(bb.bits(), n)))
     }
 
     // EBNF: EncodedScore
-    fn decode_move(&mut self, movetext: &[u8], piece_id: u8, occupied: Bitboard) -> Move {
-        let pos = &self.entry.pos;
+    fn decode_move(&mut self, movetext: &[u8], piece_id: u8, occupied: Bitboard) -> Result<Move> {
+        let pos = self.entry.pos;
 
         let side_to_move = pos.side_to_move();
         let our_pieces = pos.pieces_bb(side_to_move);
-        let idx = nth_set_bit_index(our_pieces.bits(), piece_id as u64);
 
-        let from = Square::new(idx);
+        let from = Self::nth_square(our_pieces, piece_id as u64)?;
 
         let piece_type = pos.piece_at(from).piece_type();
 
         match piece_type {
             PieceType::Pawn => {
                 let promotion_rank = Rank::last_pawn_rank(side_to_move);
-                let start_rank = Rank::last_pawn_rank(!side_to_move);
+                let start_rank = Rank::pawn_start_rank(side_to_move);
                 let forward = FlatSquareOffset::forward(side_to_move);
 
                 let ep_square = pos.ep_square();
@@ -134,30 +174,21 @@ impl PackedMoveScoreListReader {
                 let destinations_count = destinations.count();
 
                 if from.rank() == promotion_rank {
-                    let move_id = self.reader.extract_bits_le8(
-                        movetext,
-                        used_bits_safe((destinations_count * 4) as u64),
-                    );
-                    let pt =
-                        PieceType::from_ordinal(PieceType::Knight.ordinal() + (move_id % 4) as u8);
+                    let move_id = self.extract_bits(movetext, (destinations_count * 4) as u64)?;
+                    let pt = PieceType::from_ordinal(PieceType::Knight.ordinal() + (move_id % 4));
                     let promoted_piece = Piece::new(pt, side_to_move);
-                    let to =
-                        Square::new(nth_set_bit_index(destinations.bits(), move_id as u64 / 4));
+                    let to = Self::nth_square(destinations, move_id as u64 / 4)?;
 
-                    Move::promotion(from, to, promoted_piece)
+                    Ok(Move::promotion(from, to, promoted_piece))
                 } else {
-                    let move_id = self
-                        .reader
-                        .extract_bits_le8(movetext, used_bits_safe(destinations_count as u64));
-
-                    let idx = nth_set_bit_index(destinations.bits(), move_id as u64);
+                    let move_id = self.extract_bits(movetext, destinations_count as u64)?;
 
-                    let to = Square::new(idx);
+                    let to = Self::nth_square(destinations, move_id as u64)?;
 
                     if to == ep_square {
-                        Move::en_passant(from, to)
+                        Ok(Move::en_passant(from, to))
                     } else {
-                        Move::normal(from, to)
+                        Ok(Move::normal(from, to))
                     }
                 }
             }
@@ -174,10 +205,7 @@ impl PackedMoveScoreListReader {
                     (castling_rights & our_castling_rights_mask).count_ones() as usize;
 
                 let offset = attacks_size as usize + num_castlings;
-                let move_id = self
-                    .reader
-                    .extract_bits_le8(movetext, used_bits_safe(offset as u64))
-                    as u32;
+                let move_id = self.extract_bits(movetext, offset as u64)? as u32;
 
                 if move_id >= attacks_size {
                     let idx = move_id - attacks_size;
@@ -192,22 +220,19 @@ impl PackedMoveScoreListReader {
                         CastleType::Short
                     };
 
-                    Move::from_castle(castle_type, side_to_move)
+                    Ok(Move::from_castle(castle_type, side_to_move))
                 } else {
-                    let to = Square::new(nth_set_bit_index(attacks.bits(), move_id as u64));
-                    Move::normal(from, to)
+                    let to = Self::nth_square(attacks, move_id as u64)?;
+                    Ok(Move::normal(from, to))
                 }
             }
 
             // All other pieces (Queen, Rook, Bishop, Knight)
             _ => {
                 let attacks = attacks::piece_attacks(piece_type, from, occupied) & !our_pieces;
-                let move_id = self
-                    .reader
-                    .extract_bits_le8(movetext, used_bits_safe(attacks.count() as u64));
-                let idx = nth_set_bit_index(attacks.bits(), move_id as u64);
-                let to = Square::new(idx);
-                Move::normal(from, to)
+                let move_id = self.extract_bits(movetext, attacks.count() as u64)?;
+                let to = Self::nth_square(attacks, move_id as u64)?;
+                Ok(Move::normal(from, to))
             }
         }
     }
@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use crate::chess::position::Position;
+use crate::common::compressed_position::CompressedPosition;
+
+use super::compressed_reader::CompressedReaderError;
+
+type Result<T> = std::result::Result<T, CompressedReaderError>;
+
+/// Reads a plain stream of 24 byte big endian `CompressedPosition` records,
+/// without chunk headers or any of the move, score and result fields.
+#[derive(Debug)]
+pub struct PositionReader<R: Read> {
+    input: R,
+    is_end: bool,
+}
+
+impl<R: Read> PositionReader<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            input,
+            is_end: false,
+        }
+    }
+
+    // Fill the buffer, returns the number of bytes read which is only
+    // less than the buffer size at the end of the stream
+    fn read_record(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+
+        while filled < buffer.len() {
+            match self.input.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(filled)
+    }
+}
+
+impl<R: Read> Iterator for PositionReader<R> {
+    type Item = Result<Position>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_end {
+            return None;
+        }
+
+        let mut buffer = [0u8; 24];
+
+        let result = match self.read_record(&mut buffer) {
+            Ok(0) => {
+                self.is_end = true;
+                return None;
+            }
+            Ok(n) if n < buffer.len() => Err(CompressedReaderError::InvalidFormat(format!(
+                "Truncated position record of {} bytes",
+                n
+            ))),
+            Ok(_) => Ok(CompressedPosition::read_from_big_endian(&buffer).decompress()),
+            Err(e) => Err(e.into()),
+        };
+
+        self.is_end = result.is_err();
+
+        Some(result)
+    }
+}
+
+/// Read the positions of a file of 24 byte `CompressedPosition` records.
+/// Failing to open the file is returned as the first item.
+pub fn read_positions<P: AsRef<Path>>(path: P) -> impl Iterator<Item = Result<Position>> {
+    let (reader, error) = match File::open(path) {
+        Ok(file) => (Some(PositionReader::new(BufReader::new(file))), None),
+        Err(e) => (None, Some(Err(e.into()))),
+    };
+
+    error.into_iter().chain(reader.into_iter().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_read_positions() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1",
+        ];
+        let positions: Vec<Position> = fens
+            .iter()
+            .map(|fen| Position::from_fen(fen).unwrap())
+            .collect();
+
+        let mut buffer = vec![0u8; 24 * positions.len()];
+        for (pos, record) in positions.iter().zip(buffer.chunks_mut(24)) {
+            CompressedPosition::compress(pos).write_to_big_endian(record);
+        }
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&buffer).unwrap();
+
+        let read: Vec<Position> = read_positions(file.path()).collect::<Result<_>>().unwrap();
+
+        // the move counters are not part of a compressed position
+        assert_eq!(read.len(), positions.len());
+        for (read, expected) in read.iter().zip(&positions) {
+            assert_eq!(read.canonical_key(), expected.canonical_key());
+        }
+
+        // a truncated record is an error
+        let mut reader = PositionReader::new(&buffer[..30]);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(CompressedReaderError::InvalidFormat(_)))
+        ));
+        assert!(reader.next().is_none());
+
+        let mut missing = read_positions("./test/does-not-exist.bin");
+        assert!(matches!(
+            missing.next(),
+            Some(Err(CompressedReaderError::Io(_)))
+        ));
+        assert!(missing.next().is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{Read, Seek};
+
+use crate::common::entry::TrainingDataEntry;
+
+use super::compressed_reader::{CompressedReaderError, CompressedTrainingDataEntryReader};
+
+type Result<T> = std::result::Result<T, CompressedReaderError>;
+
+/// Yields the entries of a reader in a reproducible local shuffle, see
+/// [`CompressedTrainingDataEntryReader::shuffled`].
+///
+/// Entries are picked at random from a window of decoded entries, so the
+/// continuation chains of the input are broken up. Written out again the
+/// entries mostly become stems of their own.
+#[derive(Debug)]
+pub struct ShuffledReader<T: Read + Seek> {
+    reader: CompressedTrainingDataEntryReader<T>,
+    buffer: Vec<TrainingDataEntry>,
+    window: usize,
+    state: u64,
+    is_end: bool,
+}
+
+impl<T: Read + Seek> ShuffledReader<T> {
+    pub(super) fn new(
+        reader: CompressedTrainingDataEntryReader<T>,
+        window: usize,
+        seed: u64,
+    ) -> Self {
+        let window = window.max(1);
+
+        Self {
+            reader,
+            buffer: Vec::with_capacity(window),
+            window,
+            state: seed,
+            is_end: false,
+        }
+    }
+
+    /// Return the underlying reader
+    pub fn into_inner(self) -> CompressedTrainingDataEntryReader<T> {
+        self.reader
+    }
+
+    // SplitMix64
+    fn next_random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill(&mut self) -> Result<()> {
+        while self.buffer.len() < self.window && self.reader.has_next() {
+            self.buffer.push(self.reader.try_next()?);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: Read + Seek> Iterator for ShuffledReader<T> {
+    type Item = Result<TrainingDataEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_end {
+            return None;
+        }
+
+        if let Err(e) = self.fill() {
+            self.is_end = true;
+            return Some(Err(e));
+        }
+
+        if self.buffer.is_empty() {
+            self.is_end = true;
+            return None;
+        }
+
+        let index = (self.next_random() % self.buffer.len() as u64) as usize;
+
+        Some(Ok(self.buffer.swap_remove(index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(entry: &TrainingDataEntry) -> (u64, u16, i16) {
+        (entry.pos.zobrist_hash(), entry.ply, entry.score)
+    }
+
+    #[test]
+    fn test_shuffled() {
+        // four games of three plies each
+        let bytes = std::fs::read("./test/ep1.binpack").unwrap().repeat(4);
+
+        let mut input = Vec::new();
+        let mut reader = CompressedTrainingDataEntryReader::from_bytes(bytes.clone()).unwrap();
+        while reader.has_next() {
+            input.push(reader.next());
+        }
+
+        let shuffle = |window, seed| {
+            CompressedTrainingDataEntryReader::from_bytes(bytes.clone())
+                .unwrap()
+                .shuffled(window, seed)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+        };
+
+        let first = shuffle(5, 42);
+        assert_eq!(first, shuffle(5, 42));
+        assert_ne!(first, input);
+
+        let mut sorted = first.iter().map(key).collect::<Vec<_>>();
+        let mut expected = input.iter().map(key).collect::<Vec<_>>();
+        sorted.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(sorted, expected);
+
+        // a window of one keeps the input order
+        assert_eq!(shuffle(1, 42), input);
+        assert_eq!(shuffle(0, 7), input);
+    }
+}
@@ -0,0 +1,138 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::{
+    CompressedReaderError, CompressedTrainingDataEntryReader, CompressedTrainingDataEntryWriter,
+    CompressedWriterError, TrainingDataEntry,
+};
+
+#[derive(Debug, Error)]
+pub enum SplitError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Reader error: {0}")]
+    Reader(#[from] CompressedReaderError),
+    #[error("Writer error: {0}")]
+    Writer(#[from] CompressedWriterError),
+    #[error("Number of shards must be at least 1")]
+    NoShards,
+}
+
+/// Split the binpack at `input` into `num_shards` files named
+/// `{output_prefix}_0.binpack` to `{output_prefix}_{N-1}.binpack`.
+/// Games are dealt out round-robin and never split across shards.
+/// Returns the number of entries written to each shard.
+pub fn split_file<P: AsRef<Path>>(
+    input: P,
+    output_prefix: &str,
+    num_shards: usize,
+) -> Result<Vec<u64>, SplitError> {
+    if num_shards == 0 {
+        return Err(SplitError::NoShards);
+    }
+
+    let mut reader = CompressedTrainingDataEntryReader::open(input)?;
+
+    let mut writers = (0..num_shards)
+        .map(|i| {
+            let file = File::create(format!("{}_{}.binpack", output_prefix, i))?;
+            Ok(CompressedTrainingDataEntryWriter::new(file)?)
+        })
+        .collect::<Result<Vec<_>, SplitError>>()?;
+
+    let mut counts = vec![0; num_shards];
+    let mut shard = num_shards - 1;
+    let mut last: Option<TrainingDataEntry> = None;
+
+    while reader.has_next() {
+        let entry = reader.try_next()?;
+
+        // a new game goes to the next shard
+        if !last.is_some_and(|last| last.is_continuation(&entry)) {
+            shard = (shard + 1) % num_shards;
+        }
+
+        writers[shard].write_entry(&entry)?;
+        counts[shard] += 1;
+        last = Some(entry);
+    }
+
+    for writer in &mut writers {
+        writer.flush_chunk()?;
+    }
+
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::{position::Position, r#move::Move};
+
+    #[test]
+    fn test_split_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.binpack");
+        let prefix = dir.path().join("shard");
+        let prefix = prefix.to_str().unwrap();
+
+        // three distinct games of different lengths from the start position
+        let games = [
+            &["e2e4", "e7e5", "g1f3"][..],
+            &["d2d4", "d7d5"][..],
+            &["c2c4"][..],
+        ]
+        .map(|line| {
+            let mut pos = Position::new();
+            line.iter()
+                .enumerate()
+                .map(|(i, uci)| {
+                    let mv = Move::from_uci(&pos, uci).unwrap();
+                    let entry = TrainingDataEntry {
+                        pos,
+                        mv,
+                        score: 10 * i as i16,
+                        ply: i as u16,
+                        result: if i % 2 == 0 { 1 } else { -1 },
+                    };
+                    pos.do_move(mv);
+                    entry
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut writer =
+            CompressedTrainingDataEntryWriter::new(File::create(&input).unwrap()).unwrap();
+        for entry in games.iter().flatten() {
+            writer.write_entry(entry).unwrap();
+        }
+        drop(writer);
+
+        let counts = split_file(&input, prefix, 2).unwrap();
+        assert_eq!(counts, vec![4, 2]);
+
+        let read_all = |path: &str| {
+            let mut reader = CompressedTrainingDataEntryReader::open(path).unwrap();
+            let mut entries = Vec::new();
+            while reader.has_next() {
+                entries.push(reader.next());
+            }
+            entries
+        };
+
+        // round-robin deals the first and third game to shard 0
+        assert_eq!(
+            read_all(&format!("{}_0.binpack", prefix)),
+            [games[0].clone(), games[2].clone()].concat()
+        );
+        assert_eq!(read_all(&format!("{}_1.binpack", prefix)), games[1]);
+
+        assert!(matches!(
+            split_file(&input, prefix, 0),
+            Err(SplitError::NoShards)
+        ));
+    }
+}
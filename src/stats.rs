@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use crate::{CompressedReaderError, CompressedTrainingDataEntryReader, TrainingDataEntry};
+
+/// Summary statistics of a dataset, computed in a single pass over the entries.
+#[derive(Debug, Clone, Default)]
+pub struct DatasetStats {
+    /// Number of entries
+    pub count: u64,
+    /// Number of games, an entry which doesn't continue the previous one starts a new game
+    pub games: u64,
+    pub score_min: i16,
+    pub score_max: i16,
+    pub score_mean: f64,
+    /// Population standard deviation of the scores
+    pub score_stddev: f64,
+    /// Number of losses, draws and wins from the side to move's point of view
+    pub result_counts: [u64; 3],
+    /// Average number of entries per game
+    pub avg_game_len: f64,
+
+    // sum of squared differences from the mean, see Welford's algorithm
+    score_m2: f64,
+    last_entry: Option<TrainingDataEntry>,
+}
+
+impl DatasetStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an entry to the statistics, entries must be passed in file order
+    /// for the game boundaries to be detected.
+    pub fn accumulate(&mut self, entry: &TrainingDataEntry) {
+        if self.count == 0 {
+            self.score_min = entry.score;
+            self.score_max = entry.score;
+        } else {
+            self.score_min = self.score_min.min(entry.score);
+            self.score_max = self.score_max.max(entry.score);
+        }
+
+        self.count += 1;
+
+        let score = entry.score as f64;
+        let delta = score - self.score_mean;
+        self.score_mean += delta / self.count as f64;
+        self.score_m2 += delta * (score - self.score_mean);
+        self.score_stddev = (self.score_m2 / self.count as f64).sqrt();
+
+        if let Some(index) = match entry.result {
+            -1 => Some(0),
+            0 => Some(1),
+            1 => Some(2),
+            _ => None,
+        } {
+            self.result_counts[index] += 1;
+        }
+
+        if !self
+            .last_entry
+            .is_some_and(|last| last.is_continuation(entry))
+        {
+            self.games += 1;
+        }
+
+        self.last_entry = Some(*entry);
+        self.avg_game_len = self.count as f64 / self.games as f64;
+    }
+}
+
+/// Stream the binpack at the given path and compute its statistics.
+pub fn compute_stats<P: AsRef<Path>>(path: P) -> Result<DatasetStats, CompressedReaderError> {
+    let mut reader = CompressedTrainingDataEntryReader::open(path)?;
+    let mut stats = DatasetStats::new();
+
+    while reader.has_next() {
+        stats.accumulate(&reader.try_next()?);
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_stats() {
+        let stats = compute_stats("./test/ep1.binpack").unwrap();
+
+        let scores = [-201.0, 254.0, -220.0];
+        let mean = scores.iter().sum::<f64>() / 3.0;
+        let variance = scores.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / 3.0;
+
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.games, 1);
+        assert_eq!(stats.score_min, -220);
+        assert_eq!(stats.score_max, 254);
+        assert!((stats.score_mean - mean).abs() < 1e-9);
+        assert!((stats.score_stddev - variance.sqrt()).abs() < 1e-9);
+        assert_eq!(stats.result_counts, [0, 3, 0]);
+        assert_eq!(stats.avg_game_len, 3.0);
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::{
+    CompressedReaderError, CompressedTrainingDataEntryReader, CompressedTrainingDataEntryWriter,
+    CompressedWriterError,
+};
+
+#[derive(Debug, Error)]
+pub enum TranscodeError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Reader error: {0}")]
+    Reader(#[from] CompressedReaderError),
+    #[error("Writer error: {0}")]
+    Writer(#[from] CompressedWriterError),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TranscodeOptions {
+    /// End a chunk wherever the input ends one instead of using the
+    /// default chunk size of the writer. Without filtering the output
+    /// then matches a Stockfish written input byte for byte.
+    pub preserve_chunks: bool,
+}
+
+/// Decode the binpack at `input` and encode its entries again to `output`.
+/// Returns the number of entries written.
+pub fn transcode<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    options: &TranscodeOptions,
+) -> Result<u64, TranscodeError> {
+    let mut reader = CompressedTrainingDataEntryReader::open(input)?;
+    let mut writer = CompressedTrainingDataEntryWriter::new(File::create(output)?)?;
+
+    let mut chunk_index = reader.current_chunk_index();
+    let mut count = 0;
+
+    while reader.has_next() {
+        if options.preserve_chunks && reader.current_chunk_index() != chunk_index {
+            writer.flush_chunk()?;
+            chunk_index = reader.current_chunk_index();
+        }
+
+        writer.write_entry(&reader.try_next()?)?;
+        count += 1;
+    }
+
+    writer.flush_chunk()?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcode_preserve_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("out.binpack");
+
+        let preserve = TranscodeOptions {
+            preserve_chunks: true,
+        };
+
+        let count = transcode("./test/ep1.binpack", &output, &preserve).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            std::fs::read(&output).unwrap(),
+            std::fs::read("./test/ep1.binpack").unwrap()
+        );
+
+        // three chunks are only kept apart when preserving them
+        let input = dir.path().join("in.binpack");
+        let chunks = std::fs::read("./test/ep1.binpack").unwrap().repeat(3);
+        std::fs::write(&input, &chunks).unwrap();
+
+        assert_eq!(transcode(&input, &output, &preserve).unwrap(), 9);
+        assert_eq!(std::fs::read(&output).unwrap(), chunks);
+
+        assert_eq!(
+            transcode(&input, &output, &TranscodeOptions::default()).unwrap(),
+            9
+        );
+        let merged = std::fs::read(&output).unwrap();
+        assert!(merged.len() < chunks.len());
+        assert_eq!(&merged[..4], b"BINP");
+        assert_eq!(
+            u32::from_le_bytes(merged[4..8].try_into().unwrap()) as usize,
+            merged.len() - 8
+        );
+    }
+}
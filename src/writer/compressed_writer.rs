@@ -3,7 +3,7 @@ use std::io::{self};
 use thiserror::Error;
 
 use crate::{
-    chess::{position::Position, r#move::Move},
+    chess::{color::Color, position::Position, r#move::Move},
     common::{
         compressed_training_file_writer::CompressedTrainingDataFileWriter,
         entry::PackedTrainingDataEntry, entry::TrainingDataEntry,
@@ -28,8 +28,37 @@ pub enum CompressedWriterError {
     EndOfFile,
 }
 
+/// The variant of a [`CompressedWriterError`] without its payload, for comparisons.
+/// IO errors are represented by their [`io::ErrorKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriterErrorKind {
+    Io(io::ErrorKind),
+    InvalidFormat,
+    EndOfFile,
+}
+
+impl CompressedWriterError {
+    /// Get the kind of the error
+    pub fn kind(&self) -> WriterErrorKind {
+        match self {
+            CompressedWriterError::Io(e) => WriterErrorKind::Io(e.kind()),
+            CompressedWriterError::InvalidFormat(_) => WriterErrorKind::InvalidFormat,
+            CompressedWriterError::EndOfFile => WriterErrorKind::EndOfFile,
+        }
+    }
+}
+
 type Result<T> = std::result::Result<T, CompressedWriterError>;
 
+/// Callback invoked with the number of bytes of every chunk written to the file
+struct ChunkFlushCallback(Box<dyn FnMut(usize) + Send>);
+
+impl std::fmt::Debug for ChunkFlushCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ChunkFlushCallback")
+    }
+}
+
 /// Write Stockfish binpacks from TrainingDataEntry's
 /// to a file.
 #[derive(Debug)]
@@ -40,6 +69,8 @@ pub struct CompressedTrainingDataEntryWriter<T: Write> {
     packed_size: usize,
     packed_entries: Vec<u8>,
     is_first: bool,
+    on_chunk_flush: Option<ChunkFlushCallback>,
+    validate_chains: bool,
 }
 
 impl<T: Write> CompressedTrainingDataEntryWriter<T> {
@@ -59,21 +90,28 @@ impl<T: Write> CompressedTrainingDataEntryWriter<T> {
     pub fn new(file: T) -> Result<Self> {
         let writer = Self {
             output_file: Some(CompressedTrainingDataFileWriter::new(file)?),
-            last_entry: TrainingDataEntry {
-                ply: 0xFFFF, // never a continuation
-                result: 0x7FFF,
-                pos: Position::default(),
-                mv: Move::default(),
-                score: 0,
-            },
+            last_entry: Self::no_entry(),
             movelist: PackedMoveScoreList::new(),
             packed_size: 0,
             packed_entries: vec![0u8; SUGGESTED_CHUNK_SIZE + MAX_MOVELIST_SIZE],
             is_first: true,
+            on_chunk_flush: None,
+            validate_chains: false,
         };
         Ok(writer)
     }
 
+    // Placeholder for the last entry which is never continued
+    fn no_entry() -> TrainingDataEntry {
+        TrainingDataEntry {
+            ply: 0xFFFF, // never a continuation
+            result: 0x7FFF,
+            pos: Position::default(),
+            mv: Move::default(),
+            score: 0,
+        }
+    }
+
     pub fn into_inner(&mut self) -> io::Result<T> {
         self.output_file.take().unwrap().into_inner()
     }
@@ -82,10 +120,53 @@ impl<T: Write> CompressedTrainingDataEntryWriter<T> {
         self.output_file.as_ref().unwrap().written_bytes()
     }
 
+    /// Set a callback which is invoked each time a chunk is written to the file,
+    /// with the number of bytes written including the 8 byte chunk header.
+    pub fn on_chunk_flush(&mut self, cb: impl FnMut(usize) + Send + 'static) {
+        self.on_chunk_flush = Some(ChunkFlushCallback(Box::new(cb)));
+    }
+
+    /// Builder form of [`Self::on_chunk_flush`].
+    pub fn with_on_chunk_flush(mut self, cb: impl FnMut(usize) + Send + 'static) -> Self {
+        self.on_chunk_flush(cb);
+        self
+    }
+
+    /// Reject entries whose ply and result continue the previous entry but whose
+    /// position doesn't follow from its move. Without validation such an entry
+    /// silently starts a new chain. Disabled by default.
+    pub fn with_validate_chains(mut self, enabled: bool) -> Self {
+        self.validate_chains = enabled;
+        self
+    }
+
     /// Write a single entry to the file
     pub fn write_entry(&mut self, entry: &TrainingDataEntry) -> Result<()> {
         let is_cont = self.last_entry.is_continuation(entry);
 
+        if self.validate_chains
+            && !is_cont
+            && self.last_entry.mv != Move::null()
+            && self.last_entry.ply.wrapping_add(1) == entry.ply
+            && self.last_entry.result == -entry.result
+        {
+            return Err(CompressedWriterError::InvalidFormat(format!(
+                "Position at ply {} does not follow from {} played at ply {}",
+                entry.ply,
+                self.last_entry.mv.as_uci(),
+                self.last_entry.ply
+            )));
+        }
+
+        self.write_entry_impl(entry, is_cont)
+    }
+
+    /// Write a single entry as a new stem, even if it continues the previous entry
+    pub(crate) fn write_stem(&mut self, entry: &TrainingDataEntry) -> Result<()> {
+        self.write_entry_impl(entry, false)
+    }
+
+    fn write_entry_impl(&mut self, entry: &TrainingDataEntry, is_cont: bool) -> Result<()> {
         if is_cont {
             self.movelist
                 .add_move_score(&entry.pos, entry.mv, entry.score);
@@ -95,18 +176,7 @@ impl<T: Write> CompressedTrainingDataEntryWriter<T> {
             }
 
             if self.packed_size >= SUGGESTED_CHUNK_SIZE {
-                match self
-                    .output_file
-                    .as_mut()
-                    .unwrap()
-                    .append(&self.packed_entries[..self.packed_size])
-                {
-                    Ok(_) => {}
-                    Err(e) => {
-                        return Err(CompressedWriterError::Io(e));
-                    }
-                }
-                self.packed_size = 0;
+                self.append_chunk()?;
             }
 
             let packed = PackedTrainingDataEntry::from_entry(entry);
@@ -126,6 +196,70 @@ impl<T: Write> CompressedTrainingDataEntryWriter<T> {
         Ok(())
     }
 
+    /// Write an entry for a FEN and its score, e.g. from a CSV without best moves.
+    /// The ply is derived from the move counters of the FEN and the move is stored
+    /// as a null move, so the entry has no "best move" and never starts a chain.
+    /// Score and result are relative to the side to move.
+    pub fn write_fen_score(&mut self, fen: &str, score: i16, result: i16) -> Result<()> {
+        let pos = Position::from_fen(fen)
+            .map_err(|_| CompressedWriterError::InvalidFormat(format!("Invalid FEN: {}", fen)))?;
+
+        self.write_entry(&TrainingDataEntry {
+            pos,
+            mv: Move::null(),
+            score,
+            ply: pos.ply(),
+            result,
+        })
+    }
+
+    /// Write a whole game as a single chain of entries, starting at `start`.
+    /// Each move is paired with its score, relative to the side to move.
+    /// The result is from White's point of view, 1, 0, -1 for win, draw, loss.
+    pub fn write_game(
+        &mut self,
+        start: &Position,
+        moves: &[(Move, i16)],
+        result: i16,
+    ) -> Result<()> {
+        let mut pos = *start;
+
+        for &(mv, score) in moves {
+            let entry = TrainingDataEntry {
+                pos,
+                mv,
+                score,
+                ply: pos.ply(),
+                result: if pos.side_to_move() == Color::White {
+                    result
+                } else {
+                    -result
+                },
+            };
+
+            self.write_entry(&entry)?;
+            pos.do_move(mv);
+        }
+
+        Ok(())
+    }
+
+    /// End the current chunk and write it to the file, even if it is smaller than
+    /// the suggested chunk size. The next entry starts a new chunk with a new stem.
+    pub fn flush_chunk(&mut self) -> Result<()> {
+        if self.packed_size == 0 {
+            return Ok(());
+        }
+
+        self.write_movelist();
+        self.append_chunk()?;
+
+        self.is_first = true;
+        self.last_entry = Self::no_entry();
+
+        Ok(())
+    }
+
     pub fn flush_and_end(&mut self) {
         let _ = self.flush_packed();
     }
@@ -143,18 +277,7 @@ impl<T: Write> CompressedTrainingDataEntryWriter<T> {
                 self.write_movelist();
             }
 
-            match self
-                .output_file
-                .as_mut()
-                .unwrap()
-                .append(&self.packed_entries[..self.packed_size])
-            {
-                Ok(_) => {}
-                Err(e) => {
-                    return Err(CompressedWriterError::Io(e));
-                }
-            }
-            self.packed_size = 0;
+            self.append_chunk()?;
         }
 
         if let Some(file) = self.output_file.as_mut() {
@@ -164,6 +287,21 @@ impl<T: Write> CompressedTrainingDataEntryWriter<T> {
         Ok(())
     }
 
+    /// Write the buffered entries as a chunk
+    fn append_chunk(&mut self) -> Result<()> {
+        let file = self.output_file.as_mut().unwrap();
+        let before = file.written_bytes();
+
+        file.append(&self.packed_entries[..self.packed_size])?;
+        self.packed_size = 0;
+
+        if let Some(cb) = self.on_chunk_flush.as_mut() {
+            (cb.0)((file.written_bytes() - before) as usize);
+        }
+
+        Ok(())
+    }
+
     fn write_movelist(&mut self) {
         self.packed_entries[self.packed_size] = (self.movelist.num_plies >> 8) as u8;
         self.packed_entries[self.packed_size + 1] = self.movelist.num_plies as u8;
@@ -221,7 +359,7 @@ mod tests {
 
     #[test]
     fn test_compressed_writer() {
-        let entries = vec![
+        let entries = [
             TrainingDataEntry {
                 pos: Position::from_fen("1q5b/1r5k/4p2p/1b2P1pN/3p4/6PP/1nP3B1/1Q2B1K1 w - - 0 35")
                     .unwrap(),
@@ -271,7 +409,7 @@ mod tests {
                 .read(true)
                 .write(true)
                 .create(true)
-                .append(false)
+                .truncate(true)
                 .open("test/ep_new1.binpack")
                 .unwrap();
 
@@ -293,7 +431,7 @@ mod tests {
 
     #[test]
     fn test_compressed_writer_in_memory_file() {
-        let entries = vec![
+        let entries = [
             TrainingDataEntry {
                 pos: Position::from_fen("1q5b/1r5k/4p2p/1b2P1pN/3p4/6PP/1nP3B1/1Q2B1K1 w - - 0 35")
                     .unwrap(),
@@ -358,7 +496,7 @@ mod tests {
 
     #[test]
     fn test_compressed_writer_big_score_diff() {
-        let entries = vec![
+        let entries = [
             TrainingDataEntry {
                 pos: Position::from_fen("1q5b/1r5k/4p2p/1b2P1pN/3p4/6PP/1nP3B1/1Q2B1K1 w - - 0 35")
                     .unwrap(),
@@ -409,6 +547,109 @@ mod tests {
         assert_eq!(read_bytes, expected_bytes);
     }
 
+    #[test]
+    fn test_on_chunk_flush() {
+        use std::sync::{Arc, Mutex};
+
+        let flushed = Arc::new(Mutex::new(Vec::new()));
+
+        let mut writer = CompressedTrainingDataEntryWriter::new_in_memory().unwrap();
+        let sizes = Arc::clone(&flushed);
+        writer.on_chunk_flush(move |bytes| sizes.lock().unwrap().push(bytes));
+
+        // every entry is a stem of 34 bytes, enough for more than one chunk
+        let count = SUGGESTED_CHUNK_SIZE / 34 + 100;
+        let mut pos = Position::new();
+        for i in 0..count {
+            pos.set_rule50_counter((i % 100) as u16);
+            writer
+                .write_entry(&TrainingDataEntry {
+                    pos,
+                    mv: Move::null(),
+                    score: 0,
+                    ply: 0,
+                    result: 0,
+                })
+                .unwrap();
+        }
+
+        assert_eq!(flushed.lock().unwrap().len(), 1);
+
+        let bytes = writer.into_bytes().unwrap();
+        let flushed = flushed.lock().unwrap();
+
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed.iter().sum::<usize>(), bytes.len());
+    }
+
+    #[test]
+    fn test_write_fen_score() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 3 40",
+        ];
+
+        let mut writer = CompressedTrainingDataEntryWriter::new_in_memory().unwrap();
+        writer.write_fen_score(fens[0], 35, 0).unwrap();
+        writer.write_fen_score(fens[1], -20, 0).unwrap();
+        writer.write_fen_score(fens[2], 900, 1).unwrap();
+        assert_eq!(
+            writer
+                .write_fen_score("not a fen", 0, 0)
+                .unwrap_err()
+                .kind(),
+            WriterErrorKind::InvalidFormat
+        );
+        let bytes = writer.into_bytes().unwrap();
+
+        let mut reader = crate::CompressedTrainingDataEntryReader::from_bytes(bytes).unwrap();
+        let mut entries = Vec::new();
+        while reader.has_next() {
+            entries.push(reader.next());
+        }
+
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|e| e.mv == Move::null()));
+        assert_eq!(entries[0].pos.fen().unwrap(), fens[0]);
+        assert_eq!(entries[0].ply, 1);
+        assert_eq!(entries[1].ply, 2);
+        assert_eq!(entries[1].score, -20);
+        assert_eq!(entries[2].pos.fen().unwrap(), fens[2]);
+        assert_eq!(entries[2].score, 900);
+        assert_eq!(entries[2].result, 1);
+    }
+
+    #[test]
+    fn test_write_game() {
+        let start = Position::new();
+        let mut pos = start;
+        let mut moves = Vec::new();
+
+        for san in ["e4", "e5", "Nf3", "Nc6"] {
+            let mv = Move::from_san(&pos, san).unwrap();
+            moves.push((mv, 10));
+            pos.do_move(mv);
+        }
+
+        let mut writer = CompressedTrainingDataEntryWriter::new_in_memory().unwrap();
+        writer.write_game(&start, &moves, -1).unwrap();
+        let bytes = writer.into_bytes().unwrap();
+
+        let mut reader = crate::CompressedTrainingDataEntryReader::from_bytes(bytes).unwrap();
+        let mut entries = Vec::new();
+        while reader.has_next() {
+            entries.push(reader.next());
+        }
+
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].pos, start);
+        assert_eq!(entries[3].mv, moves[3].0);
+        assert_eq!(entries[3].ply, 3);
+        assert_eq!(entries[0].result, -1);
+        assert_eq!(entries[1].result, 1);
+    }
+
     #[test]
     fn test_compressed_writer_into_bytes() {
         let entries = vec![TrainingDataEntry {
@@ -435,4 +676,45 @@ mod tests {
         assert!(!bytes.is_empty());
         assert_eq!(&bytes[..4], b"BINP");
     }
+
+    #[test]
+    fn test_validate_chains() {
+        let pos = Position::new();
+        let mv = Move::normal(Square::new(12), Square::new(28));
+
+        let first = TrainingDataEntry {
+            pos,
+            mv,
+            score: 20,
+            ply: 0,
+            result: 1,
+        };
+        // claims to continue the first entry but e2e4 wasn't played
+        let broken = TrainingDataEntry {
+            pos: pos.after_move(Move::normal(Square::new(11), Square::new(27))),
+            mv: Move::normal(Square::new(52), Square::new(36)),
+            score: -20,
+            ply: 1,
+            result: -1,
+        };
+
+        let mut writer = CompressedTrainingDataEntryWriter::new_in_memory().unwrap();
+        writer.write_entry(&first).unwrap();
+        writer.write_entry(&broken).unwrap();
+
+        let mut writer = CompressedTrainingDataEntryWriter::new_in_memory()
+            .unwrap()
+            .with_validate_chains(true);
+        writer.write_entry(&first).unwrap();
+        assert_eq!(
+            writer.write_entry(&broken).unwrap_err().kind(),
+            WriterErrorKind::InvalidFormat
+        );
+
+        let valid = TrainingDataEntry {
+            pos: pos.after_move(mv),
+            ..broken
+        };
+        writer.write_entry(&valid).unwrap();
+    }
 }
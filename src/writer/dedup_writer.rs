@@ -0,0 +1,104 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use crate::common::entry::TrainingDataEntry;
+
+use super::compressed_writer::{CompressedTrainingDataEntryWriter, CompressedWriterError};
+
+type Result<T> = std::result::Result<T, CompressedWriterError>;
+
+/// Writer which drops entries whose position was already written,
+/// keeping the first occurrence. Positions are compared by
+/// [`Position::key128`](crate::chess::position::Position::key128),
+/// so the move counters are ignored.
+///
+/// Dropping entries breaks the continuation chains of the games,
+/// every surviving entry is therefore written as a fresh stem.
+#[derive(Debug)]
+pub struct DedupWriter<T: Write> {
+    writer: CompressedTrainingDataEntryWriter<T>,
+    seen: HashSet<u128>,
+    duplicates_skipped: u64,
+}
+
+impl<T: Write> DedupWriter<T> {
+    pub fn new(file: T) -> Result<Self> {
+        Ok(Self::from_writer(CompressedTrainingDataEntryWriter::new(
+            file,
+        )?))
+    }
+
+    pub fn from_writer(writer: CompressedTrainingDataEntryWriter<T>) -> Self {
+        Self {
+            writer,
+            seen: HashSet::new(),
+            duplicates_skipped: 0,
+        }
+    }
+
+    /// Write the entry unless its position was written before
+    pub fn write_entry(&mut self, entry: &TrainingDataEntry) -> Result<()> {
+        if !self.seen.insert(entry.pos.key128()) {
+            self.duplicates_skipped += 1;
+            return Ok(());
+        }
+
+        self.writer.write_stem(entry)
+    }
+
+    /// Number of entries dropped so far
+    pub fn duplicates_skipped(&self) -> u64 {
+        self.duplicates_skipped
+    }
+
+    pub fn into_writer(self) -> CompressedTrainingDataEntryWriter<T> {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    use crate::chess::{coords::Square, position::Position, r#move::Move};
+    use crate::reader::CompressedTrainingDataEntryReader;
+
+    #[test]
+    fn test_dedup_writer() {
+        let start = Position::new();
+        let after_e4 = start.after_move(Move::normal(Square::new(12), Square::new(28)));
+
+        let entry = |pos: Position, score| TrainingDataEntry {
+            pos,
+            mv: Move::null(),
+            score,
+            ply: pos.ply(),
+            result: 0,
+        };
+
+        let mut writer =
+            DedupWriter::from_writer(CompressedTrainingDataEntryWriter::new_in_memory().unwrap());
+
+        writer.write_entry(&entry(start, 10)).unwrap();
+        writer.write_entry(&entry(after_e4, 20)).unwrap();
+        writer.write_entry(&entry(start, 30)).unwrap();
+        // only the move counters differ
+        let mut moved = start;
+        moved.set_rule50_counter(4);
+        writer.write_entry(&entry(moved, 40)).unwrap();
+
+        assert_eq!(writer.duplicates_skipped(), 2);
+
+        let bytes = writer.into_writer().into_bytes().unwrap();
+        let mut reader = CompressedTrainingDataEntryReader::new(Cursor::new(bytes)).unwrap();
+
+        let mut scores = Vec::new();
+        while reader.has_next() {
+            scores.push(reader.next().score);
+        }
+
+        assert_eq!(scores, [10, 20]);
+    }
+}
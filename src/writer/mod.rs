@@ -2,7 +2,12 @@
 
 mod bitwriter;
 mod compressed_writer;
+mod dedup_writer;
 mod move_score_list;
+mod reordering_writer;
 
 pub use compressed_writer::CompressedTrainingDataEntryWriter;
 pub use compressed_writer::CompressedWriterError;
+pub use compressed_writer::WriterErrorKind;
+pub use dedup_writer::DedupWriter;
+pub use reordering_writer::ReorderingWriter;
@@ -7,7 +7,7 @@ use crate::{
         coords::{FlatSquareOffset, Rank, Square},
         piecetype::PieceType,
         position::Position,
-        r#move::{Move, MoveType},
+        r#move::Move,
     },
     common::{
         arithmetic::{signed_to_unsigned, used_bits_safe},
@@ -83,7 +83,7 @@ impl PackedMoveScoreList {
         match pt {
             PieceType::Pawn => {
                 let second_to_last_rank = Rank::last_pawn_rank(side_to_move);
-                let start_rank = Rank::last_pawn_rank(!side_to_move);
+                let start_rank = Rank::pawn_start_rank(side_to_move);
 
                 let forward = if side_to_move == Color::White {
                     FlatSquareOffset::new(0, 1)
@@ -133,7 +133,7 @@ impl PackedMoveScoreList {
                 num_moves += attacks_size as u64;
                 num_moves += num_castling_rights as u64;
 
-                if mv.mtype() == MoveType::Castle {
+                if mv.is_castle() {
                     let long_castling_rights =
                         CastlingTraits::castling_rights(side_to_move, CastleType::Long);
 
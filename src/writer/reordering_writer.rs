@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use crate::common::entry::TrainingDataEntry;
+
+use super::compressed_writer::{CompressedTrainingDataEntryWriter, CompressedWriterError};
+
+type Result<T> = std::result::Result<T, CompressedWriterError>;
+
+/// Writer for generators that produce the entries of a game out of ply order.
+/// Entries are buffered until the game ends, then sorted by ply and written,
+/// so consecutive plies are encoded as one continuation chain.
+///
+/// A game ends when [`Self::end_game`] is called or when an entry can't belong
+/// to the buffered game, i.e. its ply was already buffered or its result doesn't
+/// match the game result seen from its side to move. Games with the same result
+/// which don't share a ply can't be told apart, call `end_game` between them.
+/// The last game is written when the writer is dropped.
+///
+/// The whole game is kept in memory until it ends, roughly
+/// `size_of::<TrainingDataEntry>()` bytes per buffered entry.
+#[derive(Debug)]
+pub struct ReorderingWriter<T: Write> {
+    writer: Option<CompressedTrainingDataEntryWriter<T>>,
+    game: Vec<TrainingDataEntry>,
+    plies: HashSet<u16>,
+}
+
+impl<T: Write> ReorderingWriter<T> {
+    pub fn new(file: T) -> Result<Self> {
+        Ok(Self::from_writer(CompressedTrainingDataEntryWriter::new(
+            file,
+        )?))
+    }
+
+    pub fn from_writer(writer: CompressedTrainingDataEntryWriter<T>) -> Self {
+        Self {
+            writer: Some(writer),
+            game: Vec::new(),
+            plies: HashSet::new(),
+        }
+    }
+
+    /// Buffer an entry of the current game, writing the buffered game first
+    /// if the entry starts a new one
+    pub fn write_entry(&mut self, entry: &TrainingDataEntry) -> Result<()> {
+        if let Some(first) = self.game.first() {
+            if self.plies.contains(&entry.ply) || game_result(first) != game_result(entry) {
+                self.end_game()?;
+            }
+        }
+
+        self.plies.insert(entry.ply);
+        self.game.push(*entry);
+
+        Ok(())
+    }
+
+    /// Number of entries buffered for the current game
+    pub fn buffered(&self) -> usize {
+        self.game.len()
+    }
+
+    /// Write the buffered entries of the current game ordered by ply
+    pub fn end_game(&mut self) -> Result<()> {
+        self.game.sort_by_key(|entry| entry.ply);
+        self.plies.clear();
+
+        let writer = self.writer.as_mut().unwrap();
+
+        for entry in self.game.drain(..) {
+            writer.write_entry(&entry)?;
+        }
+
+        Ok(())
+    }
+
+    /// End the current game and return the underlying writer
+    pub fn into_writer(mut self) -> Result<CompressedTrainingDataEntryWriter<T>> {
+        self.end_game()?;
+        Ok(self.writer.take().unwrap())
+    }
+}
+
+// Result of the game from white's point of view, equal for all entries of a game
+fn game_result(entry: &TrainingDataEntry) -> i16 {
+    if entry.ply.is_multiple_of(2) {
+        entry.result
+    } else {
+        -entry.result
+    }
+}
+
+impl<T: Write> Drop for ReorderingWriter<T> {
+    fn drop(&mut self) {
+        if self.game.is_empty() {
+            return;
+        }
+
+        if let Err(e) = self.end_game() {
+            eprintln!("Error writing buffered game: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    use crate::chess::{position::Position, r#move::Move};
+    use crate::reader::CompressedTrainingDataEntryReader;
+
+    #[test]
+    fn test_reordering_writer() {
+        let mut pos = Position::new();
+        let mut game = Vec::new();
+
+        for (i, uci) in ["e2e4", "e7e5", "g1f3"].iter().enumerate() {
+            let mv = Move::from_uci(&pos, uci).unwrap();
+            game.push(TrainingDataEntry {
+                pos,
+                mv,
+                score: 10 * i as i16,
+                ply: pos.ply(),
+                result: 0,
+            });
+            pos.do_move(mv);
+        }
+
+        let mut writer = ReorderingWriter::from_writer(
+            CompressedTrainingDataEntryWriter::new_in_memory().unwrap(),
+        );
+
+        for i in [0, 2, 1] {
+            writer.write_entry(&game[i]).unwrap();
+        }
+        assert_eq!(writer.buffered(), 3);
+
+        let bytes = writer.into_writer().unwrap().into_bytes().unwrap();
+        let mut reader = CompressedTrainingDataEntryReader::new(Cursor::new(bytes)).unwrap();
+
+        let mut entries = vec![reader.next()];
+        while reader.has_next() {
+            assert!(reader.is_next_entry_continuation());
+            entries.push(reader.next());
+        }
+
+        assert_eq!(entries, game);
+    }
+
+    #[test]
+    fn test_reordering_writer_game_boundaries() {
+        let mut pos = Position::new();
+        let mut game = Vec::new();
+
+        for uci in ["d2d4", "d7d5", "c2c4"] {
+            let mv = Move::from_uci(&pos, uci).unwrap();
+            game.push(TrainingDataEntry {
+                pos,
+                mv,
+                score: 0,
+                ply: pos.ply(),
+                result: if pos.ply().is_multiple_of(2) { 1 } else { -1 },
+            });
+            pos.do_move(mv);
+        }
+
+        let mut bytes = Vec::new();
+
+        {
+            let mut writer = ReorderingWriter::new(Cursor::new(&mut bytes)).unwrap();
+
+            // a repeated ply starts the second game
+            for i in [1, 0, 2, 2, 0, 1] {
+                writer.write_entry(&game[i]).unwrap();
+            }
+            assert_eq!(writer.buffered(), 3);
+
+            // a different game result starts the third game
+            let mut lost = game[0];
+            lost.result = -1;
+            writer.write_entry(&lost).unwrap();
+            assert_eq!(writer.buffered(), 1);
+
+            // the last game is written on drop
+        }
+
+        let mut reader = CompressedTrainingDataEntryReader::new(Cursor::new(bytes)).unwrap();
+
+        let mut entries = Vec::new();
+        let mut chains = 0;
+        while reader.has_next() {
+            if !reader.is_next_entry_continuation() {
+                chains += 1;
+            }
+            entries.push(reader.next());
+        }
+
+        assert_eq!(chains, 3);
+        assert_eq!(entries.len(), 7);
+        assert_eq!(entries[..3], game);
+        assert_eq!(entries[3..6], game);
+        assert_eq!(entries[6].result, -1);
+    }
+}